@@ -0,0 +1,88 @@
+//! Compares the big-integer backends on the RFC 5114 group and the RFC 7919
+//! ffdhe groups. Each row is the mean time per call of `compute_pair`,
+//! `solve` and `verify` over one random witness.
+//!
+//!   cargo bench --bench backends                               # num-bigint only
+//!   cargo bench --bench backends --features constant-time,gmp  # all backends
+//!   cargo bench --bench backends -- 50                         # more iterations
+
+use nmc_solution::{backend::Backend, groups, ChaumPedersen};
+use num_bigint::BigUint;
+use std::{env, hint::black_box, time::Instant};
+
+/// Mean microseconds per call of `run` over `iterations` calls
+fn time(iterations: usize, run: impl Fn()) -> f64 {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        run();
+    }
+    start.elapsed().as_micros() as f64 / iterations as f64
+}
+
+fn bench<I: Backend>(name: &str, group: &str, cp: &ChaumPedersen, iterations: usize) {
+    let Ok(other) = cp.with_backend::<I>() else {
+        println!("{:<10} {:<14} does not fit", group, name);
+        return;
+    };
+    let convert = |value: &BigUint| I::from_biguint(value).unwrap();
+
+    let x = convert(&ChaumPedersen::generate_random_below(&cp.q));
+    let k = convert(&ChaumPedersen::generate_random_below(&cp.q));
+    let c = convert(&ChaumPedersen::generate_random_below(&cp.q));
+    let (y1, y2) = other.compute_pair(&x);
+    let (r1, r2) = other.compute_pair(&k);
+    let s = other.solve(&k, &c, &x);
+
+    let compute_pair = time(iterations, || {
+        black_box(other.compute_pair(black_box(&x)));
+    });
+    let solve = time(iterations, || {
+        black_box(other.solve(black_box(&k), &c, black_box(&x)));
+    });
+    let verify = time(iterations, || {
+        assert!(black_box(other.verify(&y1, &y2, &r1, &r2, &c, &s)));
+    });
+
+    println!(
+        "{:<10} {:<14} {:>14.1} {:>10.1} {:>12.1}",
+        group, name, compute_pair, solve, verify
+    );
+}
+
+fn main() {
+    // `cargo bench` passes --bench; the first numeric argument is the iteration count
+    let iterations = env::args()
+        .filter_map(|arg| arg.parse().ok())
+        .next()
+        .unwrap_or(10);
+
+    let groups = [
+        ("rfc5114", ChaumPedersen::get_constants()),
+        ("ffdhe2048", groups::ffdhe2048()),
+        ("ffdhe3072", groups::ffdhe3072()),
+        ("ffdhe4096", groups::ffdhe4096()),
+    ];
+
+    println!("{} iterations, microseconds per call", iterations);
+    println!(
+        "{:<10} {:<14} {:>14} {:>10} {:>12}",
+        "group", "backend", "compute_pair", "solve", "verify"
+    );
+
+    for (group, (alpha, beta, p, q)) in groups {
+        let cp = ChaumPedersen { alpha, beta, p, q };
+
+        bench::<BigUint>("num-bigint", group, &cp, iterations);
+
+        #[cfg(feature = "constant-time")]
+        match cp.p.bits() {
+            0..=1024 => bench::<crypto_bigint::U1024>("crypto-bigint", group, &cp, iterations),
+            1025..=2048 => bench::<crypto_bigint::U2048>("crypto-bigint", group, &cp, iterations),
+            2049..=3072 => bench::<crypto_bigint::U3072>("crypto-bigint", group, &cp, iterations),
+            _ => bench::<crypto_bigint::U4096>("crypto-bigint", group, &cp, iterations),
+        }
+
+        #[cfg(feature = "gmp")]
+        bench::<rug::Integer>("gmp", group, &cp, iterations);
+    }
+}
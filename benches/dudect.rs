@@ -0,0 +1,105 @@
+//! dudect-style timing leakage test for the prover path.
+//!
+//! Each operation is timed on two classes of secret inputs, a fixed small
+//! one and a fresh random one, picked at random per measurement. Welch's
+//! t-test then compares the two timing distributions; |t| above 4.5 means
+//! the running time depends on the secret.
+//!
+//!   cargo bench --bench dudect                            # num-bigint backend
+//!   cargo bench --bench dudect --features constant-time   # crypto-bigint backend
+//!   cargo bench --bench dudect -- 100000                  # more measurements
+
+use nmc_solution::ChaumPedersen;
+use num_bigint::BigUint;
+use rand::Rng;
+use std::{env, hint::black_box, process, time::Instant};
+
+const THRESHOLD: f64 = 4.5;
+
+/// Welch's t statistic between two samples
+fn welch_t(a: &[f64], b: &[f64]) -> f64 {
+    let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+    let var =
+        |v: &[f64], m: f64| v.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (v.len() - 1) as f64;
+
+    let (ma, mb) = (mean(a), mean(b));
+    (ma - mb) / (var(a, ma) / a.len() as f64 + var(b, mb) / b.len() as f64).sqrt()
+}
+
+/// Times `run` on `samples` inputs drawn from class 0 or 1 and returns |t|,
+/// after dropping the slowest 5% of measurements as scheduling noise
+fn measure<I>(samples: usize, input: impl Fn(usize) -> I, run: impl Fn(&I)) -> f64 {
+    let mut rng = rand::thread_rng();
+    let mut timings: Vec<(usize, f64)> = Vec::with_capacity(samples);
+
+    for _ in 0..samples {
+        let class = rng.gen_range(0..2);
+        let value = input(class);
+
+        let start = Instant::now();
+        run(black_box(&value));
+        timings.push((class, start.elapsed().as_nanos() as f64));
+    }
+
+    let mut sorted: Vec<f64> = timings.iter().map(|(_, t)| *t).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let cutoff = sorted[sorted.len() * 95 / 100];
+
+    let class = |c: usize| -> Vec<f64> {
+        timings
+            .iter()
+            .filter(|(class, t)| *class == c && *t <= cutoff)
+            .map(|(_, t)| *t)
+            .collect()
+    };
+
+    welch_t(&class(0), &class(1)).abs()
+}
+
+fn main() {
+    // `cargo bench` passes --bench; the first numeric argument is the sample count
+    let samples = env::args()
+        .filter_map(|arg| arg.parse().ok())
+        .next()
+        .unwrap_or(10_000);
+
+    let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+    let cp = ChaumPedersen { alpha, beta, p, q };
+    let secret = |class: usize| match class {
+        0 => BigUint::from(1u32),
+        _ => ChaumPedersen::generate_random_below(&cp.q),
+    };
+
+    let backend = if cfg!(feature = "constant-time") {
+        "crypto-bigint"
+    } else {
+        "num-bigint"
+    };
+    println!("backend: {}, {} measurements per test", backend, samples);
+
+    let compute_pair = measure(samples, secret, |x| {
+        black_box(cp.compute_pair(x));
+    });
+
+    // with x = 1, k >= c * x takes solve's other branch about half the time
+    let k = ChaumPedersen::generate_random_below(&cp.q);
+    let c = ChaumPedersen::generate_random_below(&cp.q);
+    let solve = measure(samples, secret, |x| {
+        black_box(cp.solve(&k, &c, x));
+    });
+
+    let mut leaks = false;
+    for (name, t) in [("compute_pair", compute_pair), ("solve", solve)] {
+        let verdict = if t > THRESHOLD {
+            leaks = true;
+            "leakage detected"
+        } else {
+            "no leakage detected"
+        };
+        println!("{:<14} |t| = {:>8.2}  {}", name, t, verdict);
+    }
+
+    if leaks {
+        process::exit(1);
+    }
+}
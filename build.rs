@@ -1,4 +1,9 @@
 fn main() {
+    // the generated gRPC code is only used by the std server and client
+    if std::env::var_os("CARGO_FEATURE_STD").is_none() {
+        return;
+    }
+
     tonic_build::configure()
         .build_server(true)
         .out_dir("src/") // you can change the generated code's location
@@ -0,0 +1,169 @@
+#ifndef NMC_SOLUTION_H
+#define NMC_SOLUTION_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum NmcStatus {
+  NMC_STATUS_OK = 0,
+  // Well-formed inputs that do not verify
+  NMC_STATUS_INVALID = 1,
+  NMC_STATUS_NULL_POINTER = 2,
+  NMC_STATUS_BUFFER_TOO_SMALL = 3,
+  // Unknown group size or alpha, beta outside the order-q subgroup
+  NMC_STATUS_INVALID_PARAMETERS = 4,
+  // A panic caught at the boundary
+  NMC_STATUS_INTERNAL = 5,
+} NmcStatus;
+
+// Opaque group parameters
+typedef struct NmcParameters NmcParameters;
+
+// Opaque non-interactive proof
+typedef struct NmcProof NmcProof;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The group nmc-server uses for `bits` = 1024 (RFC 5114), or the RFC 7919
+// group for 2048, 3072 and 4096
+//
+// # Safety
+// `out` must be valid for writes.
+enum NmcStatus nmc_parameters_standard(size_t bits, struct NmcParameters **out);
+
+// Custom parameters; alpha and beta must lie in the order-q subgroup
+//
+// # Safety
+// Each buffer must be readable for its length and `out` valid for writes.
+enum NmcStatus nmc_parameters_new(const uint8_t *p,
+                                  size_t p_len,
+                                  const uint8_t *q,
+                                  size_t q_len,
+                                  const uint8_t *alpha,
+                                  size_t alpha_len,
+                                  const uint8_t *beta,
+                                  size_t beta_len,
+                                  struct NmcParameters **out);
+
+// # Safety
+// `params` must come from `nmc_parameters_*` and not be used afterwards.
+void nmc_parameters_free(struct NmcParameters *params);
+
+// Bytes needed for any element or scalar of the group, i.e. the size of p
+//
+// # Safety
+// `params` must be a live handle or null, which gives 0.
+size_t nmc_element_len(const struct NmcParameters *params);
+
+// (y1, y2) = (alpha^x, beta^x) to register, or (r1, r2) for a nonce x
+//
+// # Safety
+// Input buffers must be readable for their length, each output buffer
+// writable for the capacity in its length pointer.
+enum NmcStatus nmc_compute_pair(const struct NmcParameters *params,
+                                const uint8_t *x,
+                                size_t x_len,
+                                uint8_t *y1,
+                                size_t *y1_len,
+                                uint8_t *y2,
+                                size_t *y2_len);
+
+// A uniform scalar below q, for a nonce k or a challenge c
+//
+// # Safety
+// `out` must be writable for `*len` bytes.
+enum NmcStatus nmc_random_scalar(const struct NmcParameters *params, uint8_t *out, size_t *len);
+
+// s = k - c * x mod q
+//
+// # Safety
+// Input buffers must be readable for their length, `s` writable for `*s_len`.
+enum NmcStatus nmc_solve(const struct NmcParameters *params,
+                         const uint8_t *k,
+                         size_t k_len,
+                         const uint8_t *c,
+                         size_t c_len,
+                         const uint8_t *x,
+                         size_t x_len,
+                         uint8_t *s,
+                         size_t *s_len);
+
+// Ok when r1 == alpha^s * y1^c and r2 == beta^s * y2^c, Invalid otherwise
+//
+// # Safety
+// Every buffer must be readable for its length.
+enum NmcStatus nmc_verify(const struct NmcParameters *params,
+                          const uint8_t *y1,
+                          size_t y1_len,
+                          const uint8_t *y2,
+                          size_t y2_len,
+                          const uint8_t *r1,
+                          size_t r1_len,
+                          const uint8_t *r2,
+                          size_t r2_len,
+                          const uint8_t *c,
+                          size_t c_len,
+                          const uint8_t *s,
+                          size_t s_len);
+
+// Non-interactive proof of knowledge of x
+//
+// # Safety
+// `x` must be readable for `x_len` bytes and `out` valid for writes.
+enum NmcStatus nmc_prove(const struct NmcParameters *params,
+                         const uint8_t *x,
+                         size_t x_len,
+                         struct NmcProof **out);
+
+// A proof from its (r1, r2, s) bytes, as received from a prover
+//
+// # Safety
+// Each buffer must be readable for its length and `out` valid for writes.
+enum NmcStatus nmc_proof_new(const uint8_t *r1,
+                             size_t r1_len,
+                             const uint8_t *r2,
+                             size_t r2_len,
+                             const uint8_t *s,
+                             size_t s_len,
+                             struct NmcProof **out);
+
+// Writes the proof's (r1, r2, s) bytes
+//
+// # Safety
+// Each output buffer must be writable for the capacity in its length pointer.
+enum NmcStatus nmc_proof_parts(const struct NmcProof *proof,
+                               uint8_t *r1,
+                               size_t *r1_len,
+                               uint8_t *r2,
+                               size_t *r2_len,
+                               uint8_t *s,
+                               size_t *s_len);
+
+// Ok when `proof` shows log_alpha(y1) == log_beta(y2), Invalid otherwise
+//
+// # Safety
+// `y1` and `y2` must be readable for their length.
+enum NmcStatus nmc_verify_proof(const struct NmcParameters *params,
+                                const uint8_t *y1,
+                                size_t y1_len,
+                                const uint8_t *y2,
+                                size_t y2_len,
+                                const struct NmcProof *proof);
+
+// # Safety
+// `proof` must come from `nmc_prove` or `nmc_proof_new` and not be used
+// afterwards.
+void nmc_proof_free(struct NmcProof *proof);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NMC_SOLUTION_H */
@@ -0,0 +1,178 @@
+import pytest
+
+from nmc_solution import DesignatedProof, Kdf, Parameters, Proof, Signature, derive_secret
+
+
+@pytest.fixture
+def params():
+    return Parameters.rfc5114()
+
+
+def test_trivial_example():
+    params = Parameters(23, 11, 4, 9)
+    x, k, c = 6, 7, 4
+
+    y1, y2 = params.compute_pair(x)
+    assert (y1, y2) == (2, 3)
+
+    r1, r2 = params.compute_pair(k)
+    assert (r1, r2) == (8, 4)
+
+    s = params.solve(k, c, x)
+    assert s == 5
+    assert params.verify(y1, y2, r1, r2, c, s)
+
+    # fake secret
+    assert not params.verify(y1, y2, r1, r2, c, params.solve(k, c, 7))
+
+
+def test_interactive_protocol(params):
+    x = derive_secret("password", Kdf.pbkdf2_sha256(bytes(16), 10_000))
+    y1, y2 = params.compute_pair(x)
+
+    k = params.random_scalar()
+    r1, r2 = params.compute_pair(k)
+    c = params.random_scalar()
+    s = params.solve(k, c, x)
+
+    assert params.verify(y1, y2, r1, r2, c, s)
+    assert params.is_element(y1) and params.is_element(y2)
+
+
+def test_non_interactive_proof(params):
+    x = params.random_scalar()
+    y1, y2 = params.compute_pair(x)
+
+    proof = params.prove(x)
+    assert params.verify_proof(y1, y2, proof)
+    assert proof == Proof(proof.r1, proof.r2, proof.s)
+
+    # proof for another secret does not verify against (y1, y2)
+    assert not params.verify_proof(y1, y2, params.prove(x + 1))
+
+
+def test_schnorr_signature(params):
+    x = params.random_scalar()
+    y, _ = params.compute_pair(x)
+
+    signature = params.sign(x, b"message")
+    assert params.verify_signature(y, b"message", signature)
+    assert signature == Signature(signature.r, signature.s)
+
+    assert not params.verify_signature(y, b"another message", signature)
+    assert not params.verify_signature(y, b"message", params.sign(x + 1, b"message"))
+
+
+def test_designated_proof(params):
+    x = params.random_scalar()
+    y1, y2 = params.compute_pair(x)
+    w = params.random_scalar()
+    yv, _ = params.compute_pair(w)
+
+    proof = params.prove_designated(x, yv, b"session-1")
+    assert params.verify_designated(y1, y2, yv, b"session-1", proof)
+    assert not params.verify_designated(y1, y2, yv, b"session-2", proof)
+
+    # the verifier can make an accepting proof for anyone, so it convinces no one else
+    forged = params.forge_designated(w, y1, y2, b"session-1")
+    assert params.verify_designated(y1, y2, yv, b"session-1", forged)
+    assert forged != proof
+    assert forged == DesignatedProof(
+        forged.r1, forged.r2, forged.c1, forged.s1, forged.t, forged.c2, forged.s2
+    )
+
+
+def test_fiat_shamir_combinators(params):
+    secrets = [params.random_scalar() for _ in range(3)]
+    statements = [params.compute_pair(x) for x in secrets]
+
+    pair = (statements[0], statements[1])
+    proof = params.prove_and(pair, (secrets[0], secrets[1]), b"ctx")
+    assert params.verify_and(pair, b"ctx", proof)
+    assert not params.verify_and((statements[0], statements[2]), b"ctx", proof)
+
+    for index in (0, 1):
+        proof = params.prove_or(pair, index, secrets[index], b"ctx")
+        assert params.verify_or(pair, b"ctx", proof)
+        assert not params.verify_or(pair, b"other", proof)
+
+    proof = params.prove_any_of(statements, 2, secrets[2], b"ctx")
+    assert params.verify_any_of(statements, b"ctx", proof)
+    assert not params.verify_any_of(statements[:2], b"ctx", proof)
+
+    with pytest.raises(ValueError):
+        params.prove_any_of(statements, 3, secrets[2], b"ctx")
+    with pytest.raises(ValueError):
+        params.prove_or(pair, 2, secrets[0], b"ctx")
+
+
+def test_kdf():
+    kdf = Kdf.pbkdf2_sha256(bytes(16), 10_000)
+    assert (kdf.algorithm, kdf.salt, kdf.iterations) == (1, bytes(16), 10_000)
+    assert kdf == Kdf.pbkdf2_sha256(kdf.salt, kdf.iterations)
+
+    # the same x in every group
+    x = derive_secret("password", kdf)
+    assert x == derive_secret("password", kdf)
+    assert x.bit_length() > Parameters.rfc5114().q.bit_length()
+    for params in [Parameters.rfc5114(), Parameters.ffdhe2048()]:
+        y1, y2 = params.compute_pair(x)
+        assert params.verify_proof(y1, y2, params.prove(x))
+
+    assert Kdf.generate().salt != Kdf.generate().salt
+    assert Kdf.generate().iterations == 100_000
+
+    with pytest.raises(ValueError):
+        Kdf.pbkdf2_sha256(bytes(16), 1)
+    with pytest.raises(ValueError):
+        Kdf.pbkdf2_sha256(b"", 10_000)
+
+
+def test_bytes_and_ints_agree(params):
+    x = derive_secret("password", Kdf.raw())
+    assert x == int.from_bytes(b"password", "big")
+
+    y1, _ = params.compute_pair(x)
+    assert params.compute_pair(b"password")[0] == y1
+    assert params.p == int.from_bytes(params.p.to_bytes(128, "big"), "big")
+
+
+def test_parameter_sets():
+    for params, bits in [
+        (Parameters.rfc5114(), 1024),
+        (Parameters.ffdhe2048(), 2048),
+        (Parameters.ffdhe3072(), 3072),
+    ]:
+        assert params.p.bit_length() == bits
+        assert params.is_element(params.alpha) and params.is_element(params.beta)
+
+
+def test_standard_groups():
+    for group_id, named in [
+        (1, Parameters.rfc5114()),
+        (2, Parameters.ffdhe2048()),
+        (3, Parameters.ffdhe3072()),
+        (4, Parameters.ffdhe4096()),
+    ]:
+        params = Parameters.standard(group_id)
+        assert (params.p, params.q, params.alpha, params.beta) == (
+            named.p,
+            named.q,
+            named.alpha,
+            named.beta,
+        )
+
+    for group_id in [0, 5]:
+        with pytest.raises(ValueError):
+            Parameters.standard(group_id)
+
+
+def test_invalid_inputs():
+    with pytest.raises(ValueError):
+        Parameters(23, 11, 5, 9)
+
+    params = Parameters(23, 11, 4, 9)
+    with pytest.raises(OverflowError):
+        params.compute_pair(-1)
+    with pytest.raises(TypeError):
+        params.compute_pair("6")
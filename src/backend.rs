@@ -0,0 +1,185 @@
+//! Big-integer backends for `ChaumPedersen`.
+//!
+//! `ChaumPedersen<I>` runs `compute_pair`, `solve`, `verify` and `is_element`
+//! over any `I: Backend`. `BigUint` is always available and is what the rest
+//! of the crate uses; the others sit behind cargo features:
+//!
+//! - `constant-time`: `crypto_bigint::Uint<LIMBS>`, fixed-width with
+//!   constant-time exponentiation in Montgomery form, implemented in `ct`,
+//!   which `BigUint`'s prover path also runs on
+//! - `gmp`: `rug::Integer`, linked against the system libgmp
+//!
+//! Moduli are the group's p and q, so every backend may assume they are odd.
+
+use crate::ChaumPedersen;
+use core::fmt::Debug;
+use num_bigint::BigUint;
+
+pub trait Backend: Clone + Debug + PartialEq + Ord + Sized {
+    /// None when the value does not fit the backend's width
+    fn from_biguint(value: &BigUint) -> Option<Self>;
+
+    fn to_biguint(&self) -> BigUint;
+
+    fn from_u32(value: u32) -> Self;
+
+    /// self^exp mod modulus
+    fn pow_mod(&self, exp: &Self, modulus: &Self) -> Self;
+
+    /// self * other mod modulus
+    fn mul_mod(&self, other: &Self, modulus: &Self) -> Self;
+
+    /// self - other mod modulus, for any self and other
+    fn sub_mod(&self, other: &Self, modulus: &Self) -> Self;
+
+    /// (alpha^exp mod p, beta^exp mod p) on the prover path, where exp is secret
+    fn compute_pair(cp: &ChaumPedersen<Self>, exp: &Self) -> (Self, Self) {
+        (cp.alpha.pow_mod(exp, &cp.p), cp.beta.pow_mod(exp, &cp.p))
+    }
+
+    /// k - c * x mod q on the prover path, where k and x are secret
+    fn solve(cp: &ChaumPedersen<Self>, k: &Self, c: &Self, x: &Self) -> Self {
+        k.sub_mod(&c.mul_mod(x, &cp.q), &cp.q)
+    }
+}
+
+impl Backend for BigUint {
+    fn from_biguint(value: &BigUint) -> Option<Self> {
+        Some(value.clone())
+    }
+
+    fn to_biguint(&self) -> BigUint {
+        self.clone()
+    }
+
+    fn from_u32(value: u32) -> Self {
+        BigUint::from(value)
+    }
+
+    fn pow_mod(&self, exp: &Self, modulus: &Self) -> Self {
+        self.modpow(exp, modulus)
+    }
+
+    fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+        (self * other) % modulus
+    }
+
+    fn sub_mod(&self, other: &Self, modulus: &Self) -> Self {
+        (self % modulus + modulus - other % modulus) % modulus
+    }
+
+    #[cfg(feature = "constant-time")]
+    fn compute_pair(cp: &ChaumPedersen<Self>, exp: &Self) -> (Self, Self) {
+        crate::ct::compute_pair(cp, exp)
+    }
+
+    #[cfg(not(feature = "constant-time"))]
+    fn compute_pair(cp: &ChaumPedersen<Self>, exp: &Self) -> (Self, Self) {
+        let p1 = cp.alpha.modpow(exp, &cp.p);
+        let p2 = cp.beta.modpow(exp, &cp.p);
+        (p1, p2)
+    }
+
+    #[cfg(feature = "constant-time")]
+    fn solve(cp: &ChaumPedersen<Self>, k: &Self, c: &Self, x: &Self) -> Self {
+        crate::ct::solve(cp, k, c, x)
+    }
+
+    #[cfg(not(feature = "constant-time"))]
+    fn solve(cp: &ChaumPedersen<Self>, k: &Self, c: &Self, x: &Self) -> Self {
+        if *k >= c * x {
+            return (k - c * x).modpow(&BigUint::from(1u32), &cp.q);
+        }
+        // q - 0 would be q itself, which is not a scalar
+        (&cp.q - (c * x - k).modpow(&BigUint::from(1u32), &cp.q)) % &cp.q
+    }
+}
+
+#[cfg(feature = "gmp")]
+mod gmp {
+    use super::Backend;
+    use num_bigint::BigUint;
+    use rug::{integer::Order, ops::RemRounding, Integer};
+
+    impl Backend for Integer {
+        fn from_biguint(value: &BigUint) -> Option<Self> {
+            Some(Integer::from_digits(&value.to_bytes_be(), Order::MsfBe))
+        }
+
+        fn to_biguint(&self) -> BigUint {
+            BigUint::from_bytes_be(&self.to_digits::<u8>(Order::MsfBe))
+        }
+
+        fn from_u32(value: u32) -> Self {
+            Integer::from(value)
+        }
+
+        fn pow_mod(&self, exp: &Self, modulus: &Self) -> Self {
+            Integer::from(
+                self.pow_mod_ref(exp, modulus)
+                    .expect("exponent is non-negative"),
+            )
+        }
+
+        fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+            Integer::from(self * other).rem_euc(modulus)
+        }
+
+        fn sub_mod(&self, other: &Self, modulus: &Self) -> Self {
+            Integer::from(self - other).rem_euc(modulus)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    fn check_backend<I: Backend>() {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen { alpha, beta, p, q };
+        let other = cp.with_backend::<I>().unwrap();
+
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let k = ChaumPedersen::generate_random_below(&cp.q);
+        let c = ChaumPedersen::generate_random_below(&cp.q);
+        let convert = |value: &BigUint| I::from_biguint(value).unwrap();
+
+        let (y1, y2) = other.compute_pair(&convert(&x));
+        assert_eq!((y1.to_biguint(), y2.to_biguint()), cp.compute_pair(&x));
+
+        let (r1, r2) = other.compute_pair(&convert(&k));
+        let s = other.solve(&convert(&k), &convert(&c), &convert(&x));
+        assert_eq!(s.to_biguint(), cp.solve(&k, &c, &x));
+        assert!(other.verify(&y1, &y2, &r1, &r2, &convert(&c), &s));
+        assert!(other.is_element(&y1));
+
+        let s_fake = other.solve(&convert(&k), &convert(&c), &convert(&(&x + 1u32)));
+        assert!(!other.verify(&y1, &y2, &r1, &r2, &convert(&c), &s_fake));
+    }
+
+    #[test]
+    fn test_biguint_backend() {
+        check_backend::<BigUint>();
+    }
+
+    #[cfg(feature = "constant-time")]
+    #[test]
+    fn test_fixed_width_backend() {
+        check_backend::<crypto_bigint::U1024>();
+
+        // a 1024-bit group does not fit a 512-bit backend
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen { alpha, beta, p, q };
+        assert!(matches!(
+            cp.with_backend::<crypto_bigint::U512>(),
+            Err(crate::Error::InvalidParameters(_))
+        ));
+    }
+
+    #[cfg(feature = "gmp")]
+    #[test]
+    fn test_gmp_backend() {
+        check_backend::<rug::Integer>();
+    }
+}
@@ -0,0 +1,131 @@
+//! Blind Schnorr signatures.
+//!
+//! The signer sends R = alpha^k, the user blinds it into
+//! R' = R * alpha^a * y^b, computes e' = H(R', y, message) and asks the signer
+//! to answer e = e' - b instead. The signer's s = k - e * x unblinds to
+//! s' = s + a, and (R', s') is an ordinary `schnorr::Signature` on the
+//! message that the signer cannot link to the issuance in which it was made.
+//!
+//! Concurrent issuance sessions with the same key are open to the ROS attack,
+//! so signers should not keep many unanswered commitments at once.
+
+use crate::schnorr::{self, Signature};
+use crate::{ChaumPedersen, Error, Result};
+use num_bigint::BigUint;
+
+/// Signer state after sending R; `sign` consumes it so k is used only once
+#[derive(Debug)]
+pub struct BlindSigner {
+    k: BigUint,
+}
+
+impl BlindSigner {
+    pub fn new(cp: &ChaumPedersen) -> (Self, BigUint) {
+        let k = ChaumPedersen::generate_random_below(&cp.q);
+        let r = cp.alpha.modpow(&k, &cp.p);
+
+        (BlindSigner { k }, r)
+    }
+
+    /// s = k - e * x mod q
+    pub fn sign(self, cp: &ChaumPedersen, x: &BigUint, e: &BigUint) -> BigUint {
+        cp.solve(&self.k, e, x)
+    }
+}
+
+/// User state between sending the blinded challenge and unblinding
+#[derive(Debug)]
+pub struct Blinding {
+    a: BigUint,
+    r: BigUint,
+    y: BigUint,
+    message: Vec<u8>,
+}
+
+/// Blinds the signer's R for `message`; returns the challenge e to send
+pub fn blind(cp: &ChaumPedersen, y: &BigUint, r: &BigUint, message: &[u8]) -> (Blinding, BigUint) {
+    let a = ChaumPedersen::generate_random_below(&cp.q);
+    let b = ChaumPedersen::generate_random_below(&cp.q);
+
+    let r_blinded = (r * cp.alpha.modpow(&a, &cp.p) * y.modpow(&b, &cp.p)) % &cp.p;
+    let e_blinded = schnorr::challenge(cp, &r_blinded, y, message);
+    let e = (e_blinded + &cp.q - b) % &cp.q;
+
+    let blinding = Blinding {
+        a,
+        r: r_blinded,
+        y: y.clone(),
+        message: message.to_vec(),
+    };
+
+    (blinding, e)
+}
+
+impl Blinding {
+    /// s' = s + a mod q, or an error if the signer's answer does not verify
+    pub fn unblind(self, cp: &ChaumPedersen, s: &BigUint) -> Result<Signature> {
+        let signature = Signature {
+            r: self.r,
+            s: (s + &self.a) % &cp.q,
+        };
+
+        if verify(cp, &self.y, &self.message, &signature) {
+            Ok(signature)
+        } else {
+            Err(Error::Verification(
+                "blind signature does not unblind to a valid signature".into(),
+            ))
+        }
+    }
+}
+
+/// Unblinded signatures are plain Schnorr signatures
+pub fn verify(cp: &ChaumPedersen, y: &BigUint, message: &[u8], signature: &Signature) -> bool {
+    schnorr::verify(cp, y, message, signature)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    #[test]
+    fn test_blind_issuance() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let y = cp.alpha.modpow(&x, &cp.p);
+
+        let (signer, r) = BlindSigner::new(&cp);
+        let (blinding, e) = blind(&cp, &y, &r, b"token-serial");
+        let s = signer.sign(&cp, &x, &e);
+        let signature = blinding.unblind(&cp, &s).unwrap();
+
+        assert!(verify(&cp, &y, b"token-serial", &signature));
+        assert!(!verify(&cp, &y, b"other-serial", &signature));
+
+        // what the signer saw does not appear in the signature
+        assert_ne!(signature.r, r);
+        assert_ne!(signature.s, s);
+    }
+
+    #[test]
+    fn test_unblind_rejects_bad_answer() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let y = cp.alpha.modpow(&x, &cp.p);
+
+        let (signer, r) = BlindSigner::new(&cp);
+        let (blinding, e) = blind(&cp, &y, &r, b"token-serial");
+
+        let wrong_key = ChaumPedersen::generate_random_below(&cp.q);
+        let s = signer.sign(&cp, &wrong_key, &e);
+        assert!(matches!(
+            blinding.unblind(&cp, &s),
+            Err(Error::Verification(_))
+        ));
+    }
+}
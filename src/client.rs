@@ -1,110 +1,443 @@
-pub mod nillion {
-    include!("./nillion.rs");
-}
+mod proto;
 
-use nmc_solution::ChaumPedersen;
+use nmc_solution::{
+    blind, designated,
+    groups::GroupId,
+    kdf::Kdf,
+    migrate, schnorr,
+    session::Prover,
+    sigma::{AnyOf, FiatShamir},
+    ChaumPedersen, Error,
+};
 use num_bigint::BigUint;
-use std::io::stdin;
+use std::{collections::HashSet, env, io::stdin, process::ExitCode};
+use tonic::transport::Channel;
 
-use nillion::{
-    auth_client::AuthClient, AuthenticationAnswerRequest, AuthenticationChallengeRequest,
-    RegisterRequest,
+use proto::{
+    kdf_from_proto,
+    nillion::{
+        auth_client::AuthClient, AnonymousAuthenticationRequest, AnonymousChallengeRequest,
+        AuthenticationAnswerRequest, AuthenticationChallengeRequest, BlindIssuanceRequest,
+        BlindSignRequest, DesignatedAuthenticationRequest, DesignatedChallengeRequest,
+        GroupParameters, IssuerKeyRequest, MigrateUserRequest, ParametersRequest, RegisterRequest,
+        UserParametersRequest,
+    },
 };
 
+/// Fewest distinct users an anonymous login hides among; a server handing
+/// out a smaller ring, or one padded with copies, could single the user out
+const MIN_RING_SIZE: usize = 3;
+
 #[tokio::main]
-async fn main() {
-    let mut buf = String::new();
+async fn main() -> ExitCode {
+    let args: Vec<String> = env::args().collect();
+
+    let result = match args.get(1).map(String::as_str) {
+        Some("sign") if args.len() == 3 => sign(&args[2]).await,
+        Some("verify") if args.len() == 7 => {
+            verify(&args[2], &args[3], &args[4], &args[5], &args[6])
+        }
+        None => login(Mode::Interactive).await,
+        Some("designated") if args.len() == 2 => login(Mode::Designated).await,
+        Some("anonymous") if args.len() == 2 => login(Mode::Anonymous).await,
+        _ => {
+            println!("Usage:");
+            println!("  nmc-client                            register and login");
+            println!(
+                "  nmc-client designated                 login with a designated-verifier proof"
+            );
+            println!("  nmc-client anonymous                  login as some registered user");
+            println!("  nmc-client sign <message>             sign with your login secret");
+            println!("  nmc-client verify <group_id> <message> <y1> <r> <s>");
+            Ok(())
+        }
+    };
 
-    let mut client = AuthClient::connect("http://127.0.0.1:50051")
-        .await
-        .expect("Unable to connect to server");
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            println!("❌ {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
 
-    println!("✅ Connected to server.");
+/// Signs with the same secret x used for login, in the group and with the
+/// KDF the server has on record for the user, printing the group_id,
+/// y1 = alpha^x and (r, s) in hex
+async fn sign(message: &str) -> Result<(), Error> {
+    let mut buf = String::new();
+
+    let mut client = AuthClient::connect("http://127.0.0.1:50051").await?;
 
     println!("Please provide username:");
-    stdin()
-        .read_line(&mut buf)
-        .expect("Unable to read username from user input");
+    stdin().read_line(&mut buf)?;
     let username = buf.trim().to_string();
     buf.clear();
 
+    let (group, cp, kdf) = user_parameters(&mut client, &username).await?;
+
     println!("Please provide password:");
-    stdin()
-        .read_line(&mut buf)
-        .expect("Unable to read password from user input");
-    let password = BigUint::from_bytes_be(buf.trim().as_bytes());
-    buf.clear();
+    stdin().read_line(&mut buf)?;
+    let x = kdf.derive(buf.trim().as_bytes());
+
+    let (y1, _) = cp.compute_pair(&x);
+    let signature = schnorr::sign(&cp, &x, message.as_bytes());
+
+    println!("group_id: {}", u32::from(group));
+    println!("y1: {}", hex::encode(cp.encode_element(&y1)));
+    println!("r: {}", hex::encode(cp.encode_element(&signature.r)));
+    println!("s: {}", hex::encode(cp.encode_scalar(&signature.s)));
+    Ok(())
+}
 
-    let (alpha, beta, p, q) = ChaumPedersen::get_constants();
-    let cp = ChaumPedersen {
-        alpha: alpha.clone(),
-        beta: beta.clone(),
-        p: p.clone(),
-        q: q.clone(),
+/// Checks a signature printed by `sign`, in the group it names
+fn verify(group_id: &str, message: &str, y1: &str, r: &str, s: &str) -> Result<(), Error> {
+    let decode = |value: &str| -> Result<BigUint, Error> {
+        Ok(BigUint::from_bytes_be(&hex::decode(value)?))
     };
 
-    let (y1, y2) = cp.compute_pair(&password);
+    let group_id = group_id
+        .parse::<u32>()
+        .map_err(|_| Error::Decode(format!("group_id {} is not a number", group_id)))?;
+    let cp = GroupId::try_from(group_id)?.parameters();
 
-    let request = RegisterRequest {
-        user: username.clone(),
-        y1: y1.to_bytes_be(),
-        y2: y2.to_bytes_be(),
+    let signature = schnorr::Signature {
+        r: decode(r)?,
+        s: decode(s)?,
     };
 
-    let _response = client
-        .register(request)
-        .await
-        .expect("Unable to register user.");
+    if schnorr::verify(&cp, &decode(y1)?, message.as_bytes(), &signature) {
+        println!("✅ Valid signature.");
+    } else {
+        println!("❌ Invalid signature.");
+    }
+    Ok(())
+}
+
+/// The group and KDF the server has on record for `user`
+async fn user_parameters(
+    client: &mut AuthClient<Channel>,
+    user: &str,
+) -> Result<(GroupId, ChaumPedersen, Kdf), Error> {
+    let response = client
+        .get_user_parameters(UserParametersRequest {
+            user: user.to_string(),
+        })
+        .await?
+        .into_inner();
+    let group = GroupId::try_from(response.group_id)?;
+
+    Ok((group, group.parameters(), kdf_from_proto(response.kdf)?))
+}
+
+enum Mode {
+    Interactive,
+    /// A proof only the server is convinced by
+    Designated,
+    /// A proof of being one of the registered users, without saying which
+    Anonymous,
+}
+
+/// The strongest group the server offers that this client also knows,
+/// after checking the server's copy of it against ours
+async fn negotiate(client: &mut AuthClient<Channel>) -> Result<(GroupId, ChaumPedersen), Error> {
+    let response = client
+        .get_parameters(ParametersRequest {})
+        .await?
+        .into_inner();
+
+    let (id, offered) = response
+        .groups
+        .iter()
+        .filter_map(|group| Some((GroupId::try_from(group.group_id).ok()?, group)))
+        .max_by_key(|(id, _)| *id)
+        .ok_or_else(|| Error::FailedPrecondition("server offers no known group".into()))?;
+
+    // the same fixed-width encoding the server uses for every value
+    let cp = id.parameters();
+    let ours = GroupParameters {
+        group_id: id.into(),
+        p: cp.encode_element(&cp.p),
+        q: cp.encode_scalar(&cp.q),
+        alpha: cp.encode_element(&cp.alpha),
+        beta: cp.encode_element(&cp.beta),
+        fingerprint: cp.fingerprint(),
+    };
+    if *offered != ours {
+        return Err(Error::InvalidParameters(format!(
+            "server parameters for group_id {} differ from ours",
+            offered.group_id
+        )));
+    }
+
+    Ok((id, cp))
+}
+
+/// Registers a new user in the negotiated group with a fresh PBKDF2 salt,
+/// then logs in with the group and KDF the server has on record; a user
+/// already registered in a weaker group is asked to migrate after login
+async fn login(mode: Mode) -> Result<(), Error> {
+    let mut buf = String::new();
+
+    let mut client = AuthClient::connect("http://127.0.0.1:50051").await?;
+
+    println!("✅ Connected to server.");
+
+    let (group, cp) = negotiate(&mut client).await?;
+    println!("✅ Using group_id {}.", u32::from(group));
+
+    println!("Please provide username:");
+    stdin().read_line(&mut buf)?;
+    let username = buf.trim().to_string();
+    buf.clear();
+
+    let (group, cp, kdf) = match user_parameters(&mut client, &username).await {
+        Err(Error::NotFound(_)) => {
+            println!("Please provide password:");
+            stdin().read_line(&mut buf)?;
+            let kdf = Kdf::pbkdf2_sha256();
+            let password = kdf.derive(buf.trim().as_bytes());
+            buf.clear();
+
+            let (y1, y2) = cp.compute_pair(&password);
+
+            let request = RegisterRequest {
+                user: username.clone(),
+                y1: cp.encode_element(&y1),
+                y2: cp.encode_element(&y2),
+                group_id: group.into(),
+                kdf: Some((&kdf).into()),
+            };
 
-    println!("✅ Registration was successful.");
-    println!("Server Response for Register -> {:?}", _response);
+            let _response = client.register(request).await?;
+
+            println!("✅ Registration was successful.");
+            println!("Server Response for Register -> {:?}", _response);
+
+            user_parameters(&mut client, &username).await?
+        }
+        Ok((group, cp, kdf)) => {
+            println!("✅ Already registered in group_id {}.", u32::from(group));
+            (group, cp, kdf)
+        }
+        Err(error) => return Err(error),
+    };
 
     println!("Please provide the password (to login):");
-    stdin()
-        .read_line(&mut buf)
-        .expect("Could not get the password (to login) from stdin");
-    let password = BigUint::from_bytes_be(buf.trim().as_bytes());
+    stdin().read_line(&mut buf)?;
+    let password = kdf.derive(buf.trim().as_bytes());
     buf.clear();
 
-    let k = ChaumPedersen::generate_random_below(&q);
-    let (r1, r2) = cp.compute_pair(&k);
+    let response = match mode {
+        Mode::Designated => {
+            let response = client
+                .create_designated_challenge(DesignatedChallengeRequest {
+                    user: username.clone(),
+                    group_id: group.into(),
+                })
+                .await?
+                .into_inner();
+
+            let yv = cp.decode_element(&response.yv)?;
+            let proof = designated::prove(&cp, &password, &yv, response.auth_id.as_bytes());
+
+            let request = DesignatedAuthenticationRequest {
+                auth_id: response.auth_id,
+                r1: cp.encode_element(&proof.r1),
+                r2: cp.encode_element(&proof.r2),
+                c1: cp.encode_scalar(&proof.c1),
+                s1: cp.encode_scalar(&proof.s1),
+                t: cp.encode_element(&proof.t),
+                c2: cp.encode_scalar(&proof.c2),
+                s2: cp.encode_scalar(&proof.s2),
+                group_id: group.into(),
+            };
+
+            client
+                .verify_designated_authentication(request)
+                .await?
+                .into_inner()
+        }
+        Mode::Anonymous => {
+            let response = client
+                .create_anonymous_challenge(AnonymousChallengeRequest {
+                    group_id: group.into(),
+                })
+                .await?
+                .into_inner();
+
+            let ring = response
+                .y1
+                .iter()
+                .zip(&response.y2)
+                .map(|(y1, y2)| Ok((cp.decode_element(y1)?, cp.decode_element(y2)?)))
+                .collect::<Result<Vec<(BigUint, BigUint)>, Error>>()?;
+
+            let distinct: HashSet<&(BigUint, BigUint)> = ring.iter().collect();
+            if distinct.len() != ring.len() {
+                return Err(Error::Verification("Ring repeats a member.".into()));
+            }
+            if ring.len() < MIN_RING_SIZE {
+                return Err(Error::FailedPrecondition(format!(
+                    "Ring of {} users is too small to hide in, at least {} needed.",
+                    ring.len(),
+                    MIN_RING_SIZE
+                )));
+            }
+
+            let index = ring
+                .iter()
+                .position(|pair| *pair == cp.compute_pair(&password))
+                .ok_or_else(|| {
+                    Error::NotFound("Password does not match any registered user.".into())
+                })?;
+
+            println!("Proving membership among {} users", ring.len());
+
+            let (commitments, responses) = FiatShamir(AnyOf(cp.clone())).prove(
+                &ring,
+                &(index, password.clone()),
+                response.auth_id.as_bytes(),
+            )?;
+
+            let request = AnonymousAuthenticationRequest {
+                auth_id: response.auth_id,
+                r1: commitments
+                    .iter()
+                    .map(|(r1, _)| cp.encode_element(r1))
+                    .collect(),
+                r2: commitments
+                    .iter()
+                    .map(|(_, r2)| cp.encode_element(r2))
+                    .collect(),
+                c: responses.iter().map(|(c, _)| cp.encode_scalar(c)).collect(),
+                s: responses.iter().map(|(_, s)| cp.encode_scalar(s)).collect(),
+                group_id: group.into(),
+            };
+
+            client
+                .verify_anonymous_authentication(request)
+                .await?
+                .into_inner()
+        }
+        Mode::Interactive => {
+            let prover = Prover::commit(&cp, &password);
+            let (r1, r2) = prover.commitment();
+
+            let request = AuthenticationChallengeRequest {
+                user: username,
+                r1: cp.encode_element(r1),
+                r2: cp.encode_element(r2),
+                group_id: group.into(),
+            };
+
+            let response = client
+                .create_authentication_challenge(request)
+                .await?
+                .into_inner();
+            println!(
+                "Server Response for Authentication Challenge -> {:?}",
+                response
+            );
 
-    let request = AuthenticationChallengeRequest {
-        user: username,
-        r1: r1.to_bytes_be(),
-        r2: r2.to_bytes_be(),
+            let auth_id = response.auth_id;
+            let challenge = cp.decode_scalar(&response.c)?;
+            let prover = prover.receive_challenge(&challenge).respond();
+
+            println!("[auth_id -> {}][challenge -> {}]", auth_id, challenge);
+
+            let request = AuthenticationAnswerRequest {
+                auth_id,
+                s: cp.encode_scalar(prover.response()),
+                group_id: group.into(),
+            };
+
+            client.verify_authentication(request).await?.into_inner()
+        }
     };
 
-    let response = client
-        .create_authentication_challenge(request)
-        .await
-        .expect("Unable to get authentication challenge.")
-        .into_inner();
+    println!("✅Login successful! session_id: {}", response.session_id);
     println!(
-        "Server Response for Authentication Challenge -> {:?}",
+        "Server Response for Verify Authentication -> {:?}",
         response
     );
 
-    let auth_id = response.auth_id;
-    let challenge = BigUint::from_bytes_be(&response.c);
-    let s = cp.solve(&k, &challenge, &password);
+    // the server asks users in a weaker group to move, proving it is the same x
+    if response.upgrade_group_id != 0 {
+        let to = GroupId::try_from(response.upgrade_group_id)?;
+        let to_cp = to.parameters();
+        let proof = migrate::prove(&cp, &to_cp, &password, response.session_id.as_bytes())?;
+        let (y1, y2) = to_cp.compute_pair(&password);
 
-    println!("[auth_id -> {}][challenge -> {}]", auth_id, challenge);
+        client
+            .migrate_user(MigrateUserRequest {
+                session_id: response.session_id.clone(),
+                y1: to_cp.encode_element(&y1),
+                y2: to_cp.encode_element(&y2),
+                r1: proof
+                    .r
+                    .iter()
+                    .map(|(r1, _)| cp.encode_element(r1))
+                    .collect(),
+                r2: proof
+                    .r
+                    .iter()
+                    .map(|(_, r2)| cp.encode_element(r2))
+                    .collect(),
+                t1: proof
+                    .t
+                    .iter()
+                    .map(|(t1, _)| to_cp.encode_element(t1))
+                    .collect(),
+                t2: proof
+                    .t
+                    .iter()
+                    .map(|(_, t2)| to_cp.encode_element(t2))
+                    .collect(),
+                s: proof.s.iter().map(migrate::encode_response).collect(),
+                group_id: to.into(),
+            })
+            .await?;
 
-    let request = AuthenticationAnswerRequest {
-        auth_id,
-        s: s.to_bytes_be(),
-    };
+        println!("✅ Migrated to group_id {}.", u32::from(to));
+    }
+
+    // anonymous token, unlinkable to the session that paid for it
+    let issuer_key = client
+        .get_issuer_key(IssuerKeyRequest {
+            group_id: group.into(),
+        })
+        .await?
+        .into_inner();
+    let y = cp.decode_element(&issuer_key.y)?;
+
+    let issuance = client
+        .create_blind_issuance(BlindIssuanceRequest {
+            session_id: response.session_id,
+            group_id: group.into(),
+        })
+        .await?
+        .into_inner();
+
+    let serial = ChaumPedersen::generate_random_string(32);
+    let r = cp.decode_element(&issuance.r)?;
+    let (blinding, e) = blind::blind(&cp, &y, &r, serial.as_bytes());
 
     let response = client
-        .verify_authentication(request)
-        .await
-        .expect("Unable to verify authentication.")
+        .blind_sign(BlindSignRequest {
+            issuance_id: issuance.issuance_id,
+            e: cp.encode_scalar(&e),
+            group_id: group.into(),
+        })
+        .await?
         .into_inner();
 
-    println!("✅Login successful! session_id: {}", response.session_id);
-    println!(
-        "Server Response for Verify Authentication -> {:?}",
-        response
-    );
+    let token = blinding.unblind(&cp, &cp.decode_scalar(&response.s)?)?;
+
+    println!("✅ Anonymous token issued.");
+    println!("message: {}", hex::encode(serial.as_bytes()));
+    println!("r: {}", hex::encode(token.r.to_bytes_be()));
+    println!("s: {}", hex::encode(token.s.to_bytes_be()));
+    Ok(())
 }
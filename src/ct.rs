@@ -0,0 +1,191 @@
+//! Constant-time prover arithmetic, enabled by the `constant-time` feature.
+//!
+//! `BigUint::modpow` and `solve`'s `k >= c * x` branch both take time that
+//! depends on the secret. With this feature `BigUint`'s prover path runs on
+//! fixed-width `crypto_bigint` integers instead: secrets are reduced mod q
+//! a word at a time, exponents are raised over all of q's bits in Montgomery
+//! form, and the subtraction is a masked `sub_mod`. Only the public group
+//! size chooses the width, and there is no variable-time fallback: a group
+//! wider than 4096 bits panics here and is refused by `ChaumPedersen::new`.
+//!
+//! This module is also the home of the `Uint<LIMBS>` backend.
+//!
+//! Converting the secret to and from `BigUint` at the boundary is not covered;
+//! `BigUint` itself has no constant-time representation, and the number of
+//! words reduced follows the secret's length.
+
+use crate::{backend::Backend, ChaumPedersen};
+use alloc::{vec, vec::Vec};
+use crypto_bigint::{
+    modular::runtime_mod::{DynResidue, DynResidueParams},
+    Uint, U1024, U2048, U4096,
+};
+use num_bigint::BigUint;
+
+/// Widest p the prover path handles, in bits
+pub(crate) const MAX_BITS: u64 = 4096;
+
+/// Every operation runs in time that depends only on LIMBS
+impl<const LIMBS: usize> Backend for Uint<LIMBS> {
+    fn from_biguint(value: &BigUint) -> Option<Self> {
+        let bytes = value.to_bytes_be();
+        if bytes.len() > Self::BYTES {
+            return None;
+        }
+
+        let mut padded = vec![0u8; Self::BYTES];
+        padded[Self::BYTES - bytes.len()..].copy_from_slice(&bytes);
+        Some(Self::from_be_slice(&padded))
+    }
+
+    fn to_biguint(&self) -> BigUint {
+        let bytes: Vec<u8> = self
+            .as_words()
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect();
+        BigUint::from_bytes_le(&bytes)
+    }
+
+    fn from_u32(value: u32) -> Self {
+        Self::from_u32(value)
+    }
+
+    fn pow_mod(&self, exp: &Self, modulus: &Self) -> Self {
+        let params = DynResidueParams::new(modulus);
+        DynResidue::new(self, params).pow(exp).retrieve()
+    }
+
+    fn mul_mod(&self, other: &Self, modulus: &Self) -> Self {
+        Self::const_rem_wide(self.mul_wide(other), modulus).0
+    }
+
+    fn sub_mod(&self, other: &Self, modulus: &Self) -> Self {
+        let (a, _) = self.const_rem(modulus);
+        let (b, _) = other.const_rem(modulus);
+        a.sub_mod(&b, modulus)
+    }
+}
+
+/// The public values of `cp`, which fit because LIMBS is chosen from p
+fn public<const LIMBS: usize>(value: &BigUint) -> Uint<LIMBS> {
+    Uint::from_biguint(value).expect("group parameters fit the width chosen from p")
+}
+
+/// value mod q for a value of any length, so a long raw password needs no
+/// wider integer: acc = acc * 2^64 + word mod q, most significant word first
+fn reduce<const LIMBS: usize>(value: &BigUint, q: &Uint<LIMBS>) -> Uint<LIMBS> {
+    value.iter_u64_digits().rev().fold(Uint::ZERO, |acc, word| {
+        let (lo, hi) = Uint::shl_vartime_wide((acc, Uint::ZERO), 64);
+        let lo = lo.bitor(&Uint::from_u64(word));
+        Uint::const_rem_wide((lo, hi), q).0
+    })
+}
+
+fn compute_pair_fixed<const LIMBS: usize>(cp: &ChaumPedersen, exp: &BigUint) -> (BigUint, BigUint) {
+    let p = public::<LIMBS>(&cp.p);
+    let q = public::<LIMBS>(&cp.q);
+    let params = DynResidueParams::new(&p);
+
+    // alpha and beta have order q, so alpha^exp == alpha^(exp mod q)
+    let exp = reduce(exp, &q);
+    let bits = cp.q.bits() as usize;
+
+    let alpha = DynResidue::new(&public(&cp.alpha), params);
+    let beta = DynResidue::new(&public(&cp.beta), params);
+
+    (
+        alpha.pow_bounded_exp(&exp, bits).retrieve().to_biguint(),
+        beta.pow_bounded_exp(&exp, bits).retrieve().to_biguint(),
+    )
+}
+
+fn solve_fixed<const LIMBS: usize>(
+    cp: &ChaumPedersen,
+    k: &BigUint,
+    c: &BigUint,
+    x: &BigUint,
+) -> BigUint {
+    let q = public::<LIMBS>(&cp.q);
+
+    let k = reduce(k, &q);
+    let c = reduce(c, &q);
+    let x = reduce(x, &q);
+
+    let (cx, _) = Uint::const_rem_wide(c.mul_wide(&x), &q);
+    k.sub_mod(&cx, &q).to_biguint()
+}
+
+/// Panics for a group wider than `MAX_BITS`
+pub(crate) fn compute_pair(cp: &ChaumPedersen, exp: &BigUint) -> (BigUint, BigUint) {
+    match cp.p.bits() {
+        0..=1024 => compute_pair_fixed::<{ U1024::LIMBS }>(cp, exp),
+        1025..=2048 => compute_pair_fixed::<{ U2048::LIMBS }>(cp, exp),
+        2049..=MAX_BITS => compute_pair_fixed::<{ U4096::LIMBS }>(cp, exp),
+        bits => panic!(
+            "constant-time: a {}-bit group is wider than {}",
+            bits, MAX_BITS
+        ),
+    }
+}
+
+/// Panics for a group wider than `MAX_BITS`
+pub(crate) fn solve(cp: &ChaumPedersen, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
+    match cp.p.bits() {
+        0..=1024 => solve_fixed::<{ U1024::LIMBS }>(cp, k, c, x),
+        1025..=2048 => solve_fixed::<{ U2048::LIMBS }>(cp, k, c, x),
+        2049..=MAX_BITS => solve_fixed::<{ U4096::LIMBS }>(cp, k, c, x),
+        bits => panic!(
+            "constant-time: a {}-bit group is wider than {}",
+            bits, MAX_BITS
+        ),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches_variable_time() {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen { alpha, beta, p, q };
+
+        for _ in 0..8 {
+            let x = ChaumPedersen::generate_random_below(&cp.q);
+            let k = ChaumPedersen::generate_random_below(&cp.q);
+            let c = ChaumPedersen::generate_random_below(&cp.q);
+
+            let expected = (cp.alpha.modpow(&x, &cp.p), cp.beta.modpow(&x, &cp.p));
+            assert_eq!(compute_pair(&cp, &x), expected);
+
+            let cx = (&c * &x) % &cp.q;
+            let expected = (&k + &cp.q - cx) % &cp.q;
+            assert_eq!(solve(&cp, &k, &c, &x), expected);
+        }
+
+        // a password longer than q still exponentiates correctly
+        let x = BigUint::from_bytes_be(b"a password much longer than twenty bytes");
+        assert_eq!(compute_pair(&cp, &x).0, cp.alpha.modpow(&x, &cp.p));
+
+        // and so does one wider than the group itself
+        let x = BigUint::from_bytes_be(&[0xa5; 300]);
+        assert_eq!(compute_pair(&cp, &x).0, cp.alpha.modpow(&x, &cp.p));
+        let (k, c) = (BigUint::from(7u32), BigUint::from(3u32));
+        let expected = (&k + &cp.q - (&c * &x) % &cp.q) % &cp.q;
+        assert_eq!(solve(&cp, &k, &c, &x), expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "wider than 4096")]
+    fn test_wide_group_panics() {
+        let (alpha, beta, _, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen {
+            alpha,
+            beta,
+            p: BigUint::from(1u32) << 5000,
+            q,
+        };
+        compute_pair(&cp, &BigUint::from(7u32));
+    }
+}
@@ -0,0 +1,193 @@
+//! Designated-verifier Chaum-Pedersen proofs.
+//!
+//! A Fiat-Shamir proof that log_alpha(y1) == log_beta(y2) convinces anyone
+//! who sees it. Here the prover instead proves "I know x for (y1, y2) OR I
+//! know w for the verifier's key yv = alpha^w", answering the first branch
+//! honestly and simulating the second. The verifier, who knows w, learns
+//! that the prover knows x; a third party learns nothing, because the
+//! verifier could have produced the same proof for any (y1, y2) with w.
+
+use crate::ChaumPedersen;
+use num_bigint::BigUint;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesignatedProof {
+    /// Chaum-Pedersen branch: (r1, r2) = (alpha^k, beta^k)
+    pub r1: BigUint,
+    pub r2: BigUint,
+    pub c1: BigUint,
+    pub s1: BigUint,
+    /// Verifier-key branch: t = alpha^s2 * yv^c2
+    pub t: BigUint,
+    pub c2: BigUint,
+    pub s2: BigUint,
+}
+
+/// c = H(p, q, alpha, beta, yv, y1, y2, r1, r2, t, context)
+#[allow(clippy::too_many_arguments)]
+fn challenge(
+    cp: &ChaumPedersen,
+    yv: &BigUint,
+    y1: &BigUint,
+    y2: &BigUint,
+    r1: &BigUint,
+    r2: &BigUint,
+    t: &BigUint,
+    context: &[u8],
+) -> BigUint {
+    cp.hash_to_challenge(&[
+        &cp.p.to_bytes_be(),
+        &cp.q.to_bytes_be(),
+        &cp.alpha.to_bytes_be(),
+        &cp.beta.to_bytes_be(),
+        &yv.to_bytes_be(),
+        &y1.to_bytes_be(),
+        &y2.to_bytes_be(),
+        &r1.to_bytes_be(),
+        &r2.to_bytes_be(),
+        &t.to_bytes_be(),
+        context,
+    ])
+}
+
+/// c1 = c - c2 mod q
+fn split(cp: &ChaumPedersen, c: &BigUint, c2: &BigUint) -> BigUint {
+    (c + &cp.q - c2 % &cp.q) % &cp.q
+}
+
+/// Proof of knowledge of x for (y1, y2) that only the holder of yv's secret
+/// can be convinced by; `context` binds it to one session
+pub fn prove(cp: &ChaumPedersen, x: &BigUint, yv: &BigUint, context: &[u8]) -> DesignatedProof {
+    let (y1, y2) = cp.compute_pair(x);
+
+    // simulated verifier-key branch
+    let c2 = ChaumPedersen::generate_random_below(&cp.q);
+    let s2 = ChaumPedersen::generate_random_below(&cp.q);
+    let t = (cp.alpha.modpow(&s2, &cp.p) * yv.modpow(&c2, &cp.p)) % &cp.p;
+
+    // real Chaum-Pedersen branch
+    let k = ChaumPedersen::generate_random_below(&cp.q);
+    let (r1, r2) = cp.compute_pair(&k);
+
+    let c = challenge(cp, yv, &y1, &y2, &r1, &r2, &t, context);
+    let c1 = split(cp, &c, &c2);
+    let s1 = cp.solve(&k, &c1, x);
+
+    DesignatedProof {
+        r1,
+        r2,
+        c1,
+        s1,
+        t,
+        c2,
+        s2,
+    }
+}
+
+/// Rejects keys and commitments outside the order-q subgroup, whatever the
+/// caller has already checked
+pub fn verify(
+    cp: &ChaumPedersen,
+    y1: &BigUint,
+    y2: &BigUint,
+    yv: &BigUint,
+    context: &[u8],
+    proof: &DesignatedProof,
+) -> bool {
+    if ![y1, y2, yv, &proof.r1, &proof.r2, &proof.t]
+        .iter()
+        .all(|value| cp.is_element(value))
+    {
+        return false;
+    }
+
+    let c = challenge(cp, yv, y1, y2, &proof.r1, &proof.r2, &proof.t, context);
+    let t = (cp.alpha.modpow(&proof.s2, &cp.p) * yv.modpow(&proof.c2, &cp.p)) % &cp.p;
+
+    proof.c1 < cp.q
+        && proof.c2 < cp.q
+        && (&proof.c1 + &proof.c2) % &cp.q == c
+        && proof.t == t
+        && cp.verify(y1, y2, &proof.r1, &proof.r2, &proof.c1, &proof.s1)
+}
+
+/// What the designated verifier can produce with w alone, for any (y1, y2)
+pub fn forge(
+    cp: &ChaumPedersen,
+    w: &BigUint,
+    y1: &BigUint,
+    y2: &BigUint,
+    context: &[u8],
+) -> DesignatedProof {
+    let yv = cp.alpha.modpow(w, &cp.p);
+
+    // simulated Chaum-Pedersen branch
+    let c1 = ChaumPedersen::generate_random_below(&cp.q);
+    let s1 = ChaumPedersen::generate_random_below(&cp.q);
+    let r1 = (cp.alpha.modpow(&s1, &cp.p) * y1.modpow(&c1, &cp.p)) % &cp.p;
+    let r2 = (cp.beta.modpow(&s1, &cp.p) * y2.modpow(&c1, &cp.p)) % &cp.p;
+
+    // real verifier-key branch
+    let k = ChaumPedersen::generate_random_below(&cp.q);
+    let t = cp.alpha.modpow(&k, &cp.p);
+
+    let c = challenge(cp, &yv, y1, y2, &r1, &r2, &t, context);
+    let c2 = split(cp, &c, &c1);
+    let s2 = cp.solve(&k, &c2, w);
+
+    DesignatedProof {
+        r1,
+        r2,
+        c1,
+        s1,
+        t,
+        c2,
+        s2,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    #[test]
+    fn test_prove_verify() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, y2) = cp.compute_pair(&x);
+        let w = ChaumPedersen::generate_random_below(&cp.q);
+        let yv = cp.alpha.modpow(&w, &cp.p);
+
+        let proof = prove(&cp, &x, &yv, b"auth-id-1");
+        assert!(verify(&cp, &y1, &y2, &yv, b"auth-id-1", &proof));
+
+        // bound to the session and to the designated verifier
+        assert!(!verify(&cp, &y1, &y2, &yv, b"auth-id-2", &proof));
+        let other = cp.alpha.modpow(&(&w + 1u32), &cp.p);
+        assert!(!verify(&cp, &y1, &y2, &other, b"auth-id-1", &proof));
+
+        // wrong secret
+        let x_fake = ChaumPedersen::generate_random_below(&cp.q);
+        let proof = prove(&cp, &x_fake, &yv, b"auth-id-1");
+        assert!(!verify(&cp, &y1, &y2, &yv, b"auth-id-1", &proof));
+    }
+
+    #[test]
+    fn test_verifier_can_forge_for_any_user() {
+        let cp = constants();
+        let w = ChaumPedersen::generate_random_below(&cp.q);
+        let yv = cp.alpha.modpow(&w, &cp.p);
+
+        // a registered key whose secret the verifier does not know
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, y2) = cp.compute_pair(&x);
+
+        let forged = forge(&cp, &w, &y1, &y2, b"auth-id-1");
+        assert!(verify(&cp, &y1, &y2, &yv, b"auth-id-1", &forged));
+    }
+}
@@ -0,0 +1,588 @@
+//! Pedersen distributed key generation (joint Feldman VSS with complaints).
+//!
+//! Each of the n parties deals a random z_i with Feldman VSS and also
+//! broadcasts beta^z_i with a Chaum-Pedersen proof that it shares its exponent
+//! with the constant-term commitment alpha^z_i. The joint secret is
+//! x = sum z_i over the qualified dealers, which no party ever learns, while
+//! (y1, y2) = (alpha^x, beta^x) is public and can be registered with
+//! `nmc-server` like any other user key. Each party ends with a share x_j of
+//! x such that any t of them can jointly answer a login challenge.
+//!
+//! A party that receives a share inconsistent with the dealer's commitments
+//! files a complaint; the dealer must publish that share, and is disqualified
+//! if it does not or if the published share is still invalid.
+
+use crate::vss::{evaluate_commitments, feldman_verify, Share};
+use crate::{
+    poly::{check_threshold, Polynomial},
+    ChaumPedersen, Error, Proof, Result,
+};
+use num_bigint::BigUint;
+use std::collections::{BTreeSet, HashMap};
+
+/// Broadcast message of dealer i
+#[derive(Debug, Clone)]
+pub struct Dealing {
+    pub dealer: u32,
+    /// C_{i,j} = alpha^a_{i,j}, with C_{i,0} = alpha^z_i
+    pub commitments: Vec<BigUint>,
+    /// B_i = beta^z_i
+    pub beta_commitment: BigUint,
+    /// log_alpha(C_{i,0}) == log_beta(B_i)
+    pub proof: Proof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Complaint {
+    pub accuser: u32,
+    pub dealer: u32,
+}
+
+/// Dealer's public answer to a complaint; None, like no answer at all,
+/// disqualifies it
+#[derive(Debug, Clone)]
+pub struct Answer {
+    pub complaint: Complaint,
+    pub share: Option<Share>,
+}
+
+/// Result of the protocol for party `share.id`
+#[derive(Debug, Clone)]
+pub struct DkgOutput {
+    pub share: Share,
+    pub qualified: Vec<u32>,
+    pub y1: BigUint,
+    pub y2: BigUint,
+    /// alpha^x_j for every party j
+    pub verification_keys: HashMap<u32, BigUint>,
+}
+
+pub struct Party {
+    pub id: u32,
+    pub threshold: usize,
+    pub n: u32,
+    polynomial: Polynomial,
+    received: HashMap<u32, Share>,
+}
+
+impl Party {
+    /// Fails unless 1 <= threshold <= n and 1 <= id <= n
+    pub fn new(cp: &ChaumPedersen, id: u32, threshold: usize, n: u32) -> Result<Self> {
+        check_threshold(threshold, n)?;
+        if id < 1 || id > n {
+            return Err(Error::InvalidParameters(format!(
+                "party id {} out of range for {} parties",
+                id, n
+            )));
+        }
+
+        let z = ChaumPedersen::generate_random_below(&cp.q);
+
+        Ok(Party {
+            id,
+            threshold,
+            n,
+            polynomial: Polynomial::random(&z, threshold - 1, &cp.q),
+            received: HashMap::new(),
+        })
+    }
+
+    pub fn dealing(&self, cp: &ChaumPedersen) -> Dealing {
+        let z = &self.polynomial.coefficients[0];
+
+        Dealing {
+            dealer: self.id,
+            commitments: self
+                .polynomial
+                .coefficients
+                .iter()
+                .map(|a| cp.alpha.modpow(a, &cp.p))
+                .collect(),
+            beta_commitment: cp.beta.modpow(z, &cp.p),
+            proof: cp.prove(z),
+        }
+    }
+
+    /// s_{i,j} = f_i(j), sent to party j over a private channel
+    pub fn share_for(&self, recipient: u32) -> Share {
+        Share {
+            id: recipient,
+            value: self.polynomial.evaluate(&BigUint::from(recipient)),
+        }
+    }
+
+    pub fn receive_share(&mut self, dealer: u32, share: Share) {
+        self.received.insert(dealer, share);
+    }
+
+    /// One complaint per dealer whose share to us is missing or inconsistent
+    pub fn complaints(&self, cp: &ChaumPedersen, dealings: &[Dealing]) -> Vec<Complaint> {
+        dealings
+            .iter()
+            .filter(|dealing| dealing.dealer != self.id)
+            .filter(|dealing| match self.received.get(&dealing.dealer) {
+                Some(share) => {
+                    share.id != self.id || !feldman_verify(cp, &dealing.commitments, share)
+                }
+                None => true,
+            })
+            .map(|dealing| Complaint {
+                accuser: self.id,
+                dealer: dealing.dealer,
+            })
+            .collect()
+    }
+
+    /// Honest dealers publish the disputed share
+    pub fn answer(&self, complaint: &Complaint) -> Answer {
+        Answer {
+            complaint: *complaint,
+            share: Some(self.share_for(complaint.accuser)),
+        }
+    }
+
+    /// Adopts published shares that verify for our own complaints and sums
+    /// the qualified ones; fails if a qualified dealer never sent us a
+    /// share, which only happens if we did not complain about it
+    pub fn finish(
+        &mut self,
+        cp: &ChaumPedersen,
+        dealings: &[Dealing],
+        complaints: &[Complaint],
+        answers: &[Answer],
+    ) -> Result<DkgOutput> {
+        let qualified = qualified(cp, self.n, self.threshold, dealings, complaints, answers)?;
+
+        for answer in answers {
+            if answer.complaint.accuser == self.id && resolves(cp, dealings, answer) {
+                if let Some(share) = &answer.share {
+                    self.received.insert(answer.complaint.dealer, share.clone());
+                }
+            }
+        }
+        let own = self.share_for(self.id);
+        self.received.insert(self.id, own);
+
+        let mut value = BigUint::from(0u32);
+        for dealer in &qualified {
+            let share = self.received.get(dealer).ok_or_else(|| {
+                Error::FailedPrecondition(format!("no share from qualified dealer {}", dealer))
+            })?;
+            value = (value + &share.value) % &cp.q;
+        }
+
+        let (y1, y2, verification_keys) = public_key(cp, self.n, dealings, &qualified)?;
+
+        Ok(DkgOutput {
+            share: Share { id: self.id, value },
+            qualified,
+            y1,
+            y2,
+            verification_keys,
+        })
+    }
+}
+
+/// The answer publishes the accuser's share, consistent with the dealer's
+/// commitments
+fn resolves(cp: &ChaumPedersen, dealings: &[Dealing], answer: &Answer) -> bool {
+    match (
+        &answer.share,
+        dealings
+            .iter()
+            .find(|d| d.dealer == answer.complaint.dealer),
+    ) {
+        (Some(share), Some(dealing)) => {
+            share.id == answer.complaint.accuser && feldman_verify(cp, &dealing.commitments, share)
+        }
+        _ => false,
+    }
+}
+
+/// Dealers with a well-formed dealing whose every complaint was answered
+/// with a share that verifies; computed identically by every party. Answers
+/// to complaints nobody filed are ignored, since anyone can broadcast one.
+/// Fails unless 1 <= threshold <= n
+pub fn qualified(
+    cp: &ChaumPedersen,
+    n: u32,
+    threshold: usize,
+    dealings: &[Dealing],
+    complaints: &[Complaint],
+    answers: &[Answer],
+) -> Result<Vec<u32>> {
+    check_threshold(threshold, n)?;
+    let mut disqualified = BTreeSet::new();
+
+    for dealing in dealings {
+        let well_formed = dealing.dealer >= 1
+            && dealing.dealer <= n
+            && dealings
+                .iter()
+                .filter(|d| d.dealer == dealing.dealer)
+                .count()
+                == 1
+            && dealing.commitments.len() == threshold
+            && cp.verify_proof(
+                &dealing.commitments[0],
+                &dealing.beta_commitment,
+                &dealing.proof,
+            );
+        if !well_formed {
+            disqualified.insert(dealing.dealer);
+        }
+    }
+
+    // a dealer that stays silent is as bad as one that answers wrongly
+    for complaint in complaints {
+        let answered = answers
+            .iter()
+            .any(|answer| answer.complaint == *complaint && resolves(cp, dealings, answer));
+        if !answered {
+            disqualified.insert(complaint.dealer);
+        }
+    }
+
+    Ok(dealings
+        .iter()
+        .map(|dealing| dealing.dealer)
+        .filter(|dealer| !disqualified.contains(dealer))
+        .collect::<BTreeSet<u32>>()
+        .into_iter()
+        .collect())
+}
+
+/// (y1, y2) = (prod C_{i,0}, prod B_i) and alpha^x_j over the qualified dealers;
+/// fails if a qualified dealing has no commitments
+pub fn public_key(
+    cp: &ChaumPedersen,
+    n: u32,
+    dealings: &[Dealing],
+    qualified: &[u32],
+) -> Result<(BigUint, BigUint, HashMap<u32, BigUint>)> {
+    let qualified_dealings: Vec<&Dealing> = dealings
+        .iter()
+        .filter(|dealing| qualified.contains(&dealing.dealer))
+        .collect();
+
+    let mut y1 = BigUint::from(1u32);
+    let mut y2 = BigUint::from(1u32);
+    for dealing in &qualified_dealings {
+        let constant = dealing.commitments.first().ok_or_else(|| {
+            Error::InvalidParameters(format!("dealer {} has no commitments", dealing.dealer))
+        })?;
+        y1 = (y1 * constant) % &cp.p;
+        y2 = (y2 * &dealing.beta_commitment) % &cp.p;
+    }
+
+    let verification_keys = (1..=n)
+        .map(|j| {
+            let key = qualified_dealings
+                .iter()
+                .fold(BigUint::from(1u32), |acc, dealing| {
+                    (acc * evaluate_commitments(cp, &dealing.commitments, j)) % &cp.p
+                });
+            (j, key)
+        })
+        .collect();
+
+    Ok((y1, y2, verification_keys))
+}
+
+/// Misbehaviour injected into the simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Behaviour {
+    Honest,
+    /// Sends a wrong share to `victim`; publishes the right one on complaint
+    /// if `answers_complaint` is set and stays silent otherwise
+    CorruptShare {
+        victim: u32,
+        answers_complaint: bool,
+    },
+    /// Broadcasts beta^z' for some z' != z
+    InconsistentBeta,
+}
+
+/// In-process run of the protocol over a perfect broadcast channel
+pub struct Simulation<'a> {
+    pub cp: &'a ChaumPedersen,
+    pub threshold: usize,
+    pub n: u32,
+    pub behaviours: HashMap<u32, Behaviour>,
+}
+
+impl<'a> Simulation<'a> {
+    pub fn new(cp: &'a ChaumPedersen, threshold: usize, n: u32) -> Self {
+        Simulation {
+            cp,
+            threshold,
+            n,
+            behaviours: HashMap::new(),
+        }
+    }
+
+    pub fn with_behaviour(mut self, id: u32, behaviour: Behaviour) -> Self {
+        self.behaviours.insert(id, behaviour);
+        self
+    }
+
+    fn behaviour(&self, id: u32) -> Behaviour {
+        *self.behaviours.get(&id).unwrap_or(&Behaviour::Honest)
+    }
+
+    /// Outputs of all parties, indexed by id - 1
+    pub fn run(&self) -> Result<Vec<DkgOutput>> {
+        let cp = self.cp;
+        let mut parties = (1..=self.n)
+            .map(|id| Party::new(cp, id, self.threshold, self.n))
+            .collect::<Result<Vec<Party>>>()?;
+
+        // round 1: broadcast dealings, send shares privately
+        let mut dealings: Vec<Dealing> = parties.iter().map(|party| party.dealing(cp)).collect();
+        for dealing in dealings.iter_mut() {
+            if self.behaviour(dealing.dealer) == Behaviour::InconsistentBeta {
+                dealing.beta_commitment = (&dealing.beta_commitment * &cp.beta) % &cp.p;
+            }
+        }
+
+        for dealer in 1..=self.n {
+            for recipient in (1..=self.n).filter(|&r| r != dealer) {
+                let mut share = parties[dealer as usize - 1].share_for(recipient);
+                if let Behaviour::CorruptShare { victim, .. } = self.behaviour(dealer) {
+                    if victim == recipient {
+                        share.value = (share.value + 1u32) % &cp.q;
+                    }
+                }
+                parties[recipient as usize - 1].receive_share(dealer, share);
+            }
+        }
+
+        // round 2: broadcast complaints
+        let complaints: Vec<Complaint> = parties
+            .iter()
+            .flat_map(|party| party.complaints(cp, &dealings))
+            .collect();
+
+        // round 3: accused dealers answer publicly
+        let answers: Vec<Answer> = complaints
+            .iter()
+            .filter_map(|complaint| {
+                let dealer = &parties[complaint.dealer as usize - 1];
+                match self.behaviour(complaint.dealer) {
+                    Behaviour::CorruptShare {
+                        answers_complaint: false,
+                        ..
+                    } => None,
+                    _ => Some(dealer.answer(complaint)),
+                }
+            })
+            .collect();
+
+        parties
+            .iter_mut()
+            .map(|party| party.finish(cp, &dealings, &complaints, &answers))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poly::lagrange_coefficient;
+    use crate::vss::reconstruct;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    fn assert_consistent(cp: &ChaumPedersen, outputs: &[DkgOutput], threshold: usize) {
+        for output in outputs {
+            assert_eq!(output.y1, outputs[0].y1);
+            assert_eq!(output.y2, outputs[0].y2);
+            assert_eq!(output.qualified, outputs[0].qualified);
+            assert_eq!(
+                output.verification_keys[&output.share.id],
+                cp.alpha.modpow(&output.share.value, &cp.p)
+            );
+        }
+
+        // (y1, y2) is the registrable key for the reconstructed secret
+        let shares: Vec<Share> = outputs
+            .iter()
+            .take(threshold)
+            .map(|o| o.share.clone())
+            .collect();
+        let x = reconstruct(cp, &shares).unwrap();
+        assert_eq!(
+            cp.compute_pair(&x),
+            (outputs[0].y1.clone(), outputs[0].y2.clone())
+        );
+    }
+
+    #[test]
+    fn test_honest_run() {
+        let cp = constants();
+        let outputs = Simulation::new(&cp, 3, 5).run().unwrap();
+
+        assert_eq!(outputs[0].qualified, vec![1, 2, 3, 4, 5]);
+        assert_consistent(&cp, &outputs, 3);
+    }
+
+    #[test]
+    fn test_complaints() {
+        let cp = constants();
+
+        // answered complaint: dealer stays, victim adopts the published share
+        let outputs = Simulation::new(&cp, 2, 4)
+            .with_behaviour(
+                2,
+                Behaviour::CorruptShare {
+                    victim: 3,
+                    answers_complaint: true,
+                },
+            )
+            .run()
+            .unwrap();
+        assert_eq!(outputs[0].qualified, vec![1, 2, 3, 4]);
+        assert_consistent(&cp, &outputs, 2);
+
+        // silent dealer and inconsistent beta commitment are disqualified
+        let outputs = Simulation::new(&cp, 2, 4)
+            .with_behaviour(
+                1,
+                Behaviour::CorruptShare {
+                    victim: 4,
+                    answers_complaint: false,
+                },
+            )
+            .with_behaviour(3, Behaviour::InconsistentBeta)
+            .run()
+            .unwrap();
+        assert_eq!(outputs[0].qualified, vec![2, 4]);
+        assert_consistent(&cp, &outputs, 2);
+    }
+
+    #[test]
+    fn test_silent_dealer() {
+        let cp = constants();
+        let outputs = Simulation::new(&cp, 2, 3)
+            .with_behaviour(
+                2,
+                Behaviour::CorruptShare {
+                    victim: 1,
+                    answers_complaint: false,
+                },
+            )
+            .run()
+            .unwrap();
+        assert_eq!(outputs[0].qualified, vec![1, 3]);
+        assert_consistent(&cp, &outputs, 2);
+
+        // without the complaints, the victim would sum a share it never got
+        let mut victim = Party::new(&cp, 1, 2, 3).unwrap();
+        let dealings = vec![
+            victim.dealing(&cp),
+            Party::new(&cp, 2, 2, 3).unwrap().dealing(&cp),
+        ];
+        assert!(matches!(
+            victim.finish(&cp, &dealings, &[], &[]),
+            Err(Error::FailedPrecondition(_))
+        ));
+    }
+
+    #[test]
+    fn test_unfiled_answer_is_ignored() {
+        let cp = constants();
+        let parties: Vec<Party> = (1..=3)
+            .map(|id| Party::new(&cp, id, 2, 3).unwrap())
+            .collect();
+        let dealings: Vec<Dealing> = parties.iter().map(|party| party.dealing(&cp)).collect();
+
+        // anyone can broadcast a failed answer to a complaint never filed
+        let injected = Answer {
+            complaint: Complaint {
+                accuser: 1,
+                dealer: 2,
+            },
+            share: None,
+        };
+        assert_eq!(
+            qualified(&cp, 3, 2, &dealings, &[], core::slice::from_ref(&injected)),
+            Ok(vec![1, 2, 3])
+        );
+
+        // nor does it outweigh the dealer's real answer to a filed one
+        let complaint = injected.complaint;
+        let answer = parties[1].answer(&complaint);
+        assert_eq!(
+            qualified(&cp, 3, 2, &dealings, &[complaint], &[injected, answer]),
+            Ok(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn test_bad_parameters_are_rejected() {
+        let cp = constants();
+        for (id, threshold, n) in [(1, 0, 3), (1, 4, 3), (0, 2, 3), (4, 2, 3)] {
+            assert!(matches!(
+                Party::new(&cp, id, threshold, n),
+                Err(Error::InvalidParameters(_))
+            ));
+        }
+
+        // a threshold of 0 would accept a dealing with no commitments
+        let dealing = Party::new(&cp, 1, 1, 3).unwrap().dealing(&cp);
+        let empty = Dealing {
+            commitments: Vec::new(),
+            ..dealing
+        };
+        assert!(matches!(
+            qualified(&cp, 3, 0, core::slice::from_ref(&empty), &[], &[]),
+            Err(Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            public_key(&cp, 3, &[empty], &[1]),
+            Err(Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            Simulation::new(&cp, 0, 3).run(),
+            Err(Error::InvalidParameters(_))
+        ));
+    }
+
+    #[test]
+    fn test_threshold_login_against_joint_key() {
+        let cp = constants();
+        let outputs = Simulation::new(&cp, 2, 3).run().unwrap();
+        let (y1, y2) = (&outputs[0].y1, &outputs[0].y2);
+
+        // parties 1 and 3 answer a server challenge without reconstructing x
+        let signers = [&outputs[0], &outputs[2]];
+        let ids: Vec<u32> = signers.iter().map(|o| o.share.id).collect();
+        let nonces: Vec<BigUint> = signers
+            .iter()
+            .map(|_| ChaumPedersen::generate_random_below(&cp.q))
+            .collect();
+
+        let mut r1 = BigUint::from(1u32);
+        let mut r2 = BigUint::from(1u32);
+        let mut weighted = Vec::new();
+        for (output, k) in signers.iter().zip(nonces.iter()) {
+            let lambda = lagrange_coefficient(&ids, output.share.id, &cp.q);
+            let k = (k * &lambda) % &cp.q;
+            let x = (&output.share.value * &lambda) % &cp.q;
+            let (a, b) = cp.compute_pair(&k);
+            r1 = (r1 * a) % &cp.p;
+            r2 = (r2 * b) % &cp.p;
+            weighted.push((k, x));
+        }
+
+        let c = ChaumPedersen::generate_random_below(&cp.q);
+        let s = weighted.iter().fold(BigUint::from(0u32), |acc, (k, x)| {
+            (acc + cp.solve(k, &c, x)) % &cp.q
+        });
+
+        assert!(cp.verify(y1, y2, &r1, &r2, &c, &s));
+    }
+}
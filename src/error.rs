@@ -0,0 +1,154 @@
+//! The crate-wide error type.
+//!
+//! Library functions that can fail on untrusted input return `Result<T>`;
+//! under `std` an `Error` converts to and from `tonic::Status`, so the server
+//! can use `?` in its handlers and the client gets back the same variant.
+
+use alloc::string::String;
+use core::fmt;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Bytes or text that do not decode to the expected value
+    Decode(String),
+    /// Group parameters that do not describe an order-q subgroup, or other
+    /// settings out of range: a weak KDF, a bad threshold, repeated share ids
+    InvalidParameters(String),
+    /// A proof, signature or opening that does not verify
+    Verification(String),
+    /// An unknown user, auth_id or other lookup key
+    NotFound(String),
+    /// A missing or expired session
+    Unauthenticated(String),
+    /// Something that may only happen once, e.g. redeeming a token
+    AlreadyExists(String),
+    /// A request that is well-formed but not allowed in the current state
+    FailedPrecondition(String),
+    /// The connection to the server failed
+    Transport(String),
+    /// A bug or a poisoned lock, never the caller's fault
+    Internal(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Decode(message) => write!(f, "decode error: {}", message),
+            Error::InvalidParameters(message) => write!(f, "invalid parameters: {}", message),
+            Error::Verification(message) => write!(f, "verification failed: {}", message),
+            Error::NotFound(message) => write!(f, "not found: {}", message),
+            Error::Unauthenticated(message) => write!(f, "unauthenticated: {}", message),
+            Error::AlreadyExists(message) => write!(f, "already exists: {}", message),
+            Error::FailedPrecondition(message) => write!(f, "failed precondition: {}", message),
+            Error::Transport(message) => write!(f, "transport error: {}", message),
+            Error::Internal(message) => write!(f, "internal error: {}", message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+mod convert {
+    use super::Error;
+    use std::sync::PoisonError;
+    use tonic::{Code, Status};
+
+    impl std::error::Error for Error {}
+
+    impl From<Error> for Status {
+        fn from(error: Error) -> Self {
+            match error {
+                Error::Decode(message) | Error::InvalidParameters(message) => {
+                    Status::invalid_argument(message)
+                }
+                Error::Verification(message) => Status::permission_denied(message),
+                Error::NotFound(message) => Status::not_found(message),
+                Error::Unauthenticated(message) => Status::unauthenticated(message),
+                Error::AlreadyExists(message) => Status::already_exists(message),
+                Error::FailedPrecondition(message) => Status::failed_precondition(message),
+                Error::Transport(message) => Status::unavailable(message),
+                Error::Internal(message) => Status::internal(message),
+            }
+        }
+    }
+
+    /// The inverse of `From<Error> for Status`, for the client side of an RPC
+    impl From<Status> for Error {
+        fn from(status: Status) -> Self {
+            let message = status.message().to_string();
+            match status.code() {
+                Code::InvalidArgument => Error::Decode(message),
+                Code::PermissionDenied => Error::Verification(message),
+                Code::NotFound => Error::NotFound(message),
+                Code::Unauthenticated => Error::Unauthenticated(message),
+                Code::AlreadyExists => Error::AlreadyExists(message),
+                Code::FailedPrecondition => Error::FailedPrecondition(message),
+                Code::Internal => Error::Internal(message),
+                _ => Error::Transport(message),
+            }
+        }
+    }
+
+    impl From<tonic::transport::Error> for Error {
+        fn from(error: tonic::transport::Error) -> Self {
+            Error::Transport(error.to_string())
+        }
+    }
+
+    impl From<hex::FromHexError> for Error {
+        fn from(error: hex::FromHexError) -> Self {
+            Error::Decode(error.to_string())
+        }
+    }
+
+    impl From<std::io::Error> for Error {
+        fn from(error: std::io::Error) -> Self {
+            Error::Internal(error.to_string())
+        }
+    }
+
+    /// A thread panicked while holding the lock
+    impl<T> From<PoisonError<T>> for Error {
+        fn from(error: PoisonError<T>) -> Self {
+            Error::Internal(error.to_string())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use tonic::{Code, Status};
+
+    #[test]
+    fn test_status_round_trip() {
+        let errors = [
+            (Error::Decode("y1".into()), Code::InvalidArgument),
+            (Error::Verification("proof".into()), Code::PermissionDenied),
+            (Error::NotFound("alice".into()), Code::NotFound),
+            (
+                Error::Unauthenticated("session".into()),
+                Code::Unauthenticated,
+            ),
+            (Error::AlreadyExists("token".into()), Code::AlreadyExists),
+            (
+                Error::FailedPrecondition("ring".into()),
+                Code::FailedPrecondition,
+            ),
+            (Error::Transport("refused".into()), Code::Unavailable),
+            (Error::Internal("lock".into()), Code::Internal),
+        ];
+
+        for (error, code) in errors {
+            let status = Status::from(error.clone());
+            assert_eq!(status.code(), code);
+            assert_eq!(Error::from(status), error);
+        }
+
+        // parameters and encodings are both the caller's bad argument
+        let status = Status::from(Error::InvalidParameters("alpha".into()));
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "alpha");
+    }
+}
@@ -0,0 +1,417 @@
+//! Stable C ABI, enabled by the `ffi` feature. The header is generated into
+//! `include/nmc_solution.h` from this file alone by `make -C tests/c header`,
+//! and the static library is built with `--crate-type staticlib`.
+//!
+//! Parameters and proofs are opaque handles owned by the caller and released
+//! with their `_free` function. Integers are passed as big-endian byte
+//! buffers; outputs are written to a caller buffer whose capacity is passed
+//! in `*len` and replaced with the bytes written. `nmc_element_len` bounds
+//! every output. No function unwinds into C: failures, panics included,
+//! come back as an `NmcStatus`.
+
+use crate::{groups, ChaumPedersen, Proof};
+use num_bigint::BigUint;
+use std::{panic, ptr, slice};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NmcStatus {
+    Ok = 0,
+    /// Well-formed inputs that do not verify
+    Invalid = 1,
+    NullPointer = 2,
+    BufferTooSmall = 3,
+    /// Unknown group size or alpha, beta outside the order-q subgroup
+    InvalidParameters = 4,
+    /// A panic caught at the boundary
+    Internal = 5,
+}
+
+/// Opaque group parameters
+pub struct NmcParameters(ChaumPedersen);
+
+/// Opaque non-interactive proof
+pub struct NmcProof(Proof);
+
+/// Runs `f`, turning a panic into `NmcStatus::Internal`
+fn guard(f: impl FnOnce() -> Result<(), NmcStatus>) -> NmcStatus {
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(Ok(())) => NmcStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => NmcStatus::Internal,
+    }
+}
+
+unsafe fn read(bytes: *const u8, len: usize) -> Result<BigUint, NmcStatus> {
+    if bytes.is_null() {
+        return Err(NmcStatus::NullPointer);
+    }
+    Ok(BigUint::from_bytes_be(slice::from_raw_parts(bytes, len)))
+}
+
+unsafe fn write(value: &BigUint, out: *mut u8, len: *mut usize) -> Result<(), NmcStatus> {
+    if out.is_null() || len.is_null() {
+        return Err(NmcStatus::NullPointer);
+    }
+
+    let bytes = value.to_bytes_be();
+    if bytes.len() > *len {
+        return Err(NmcStatus::BufferTooSmall);
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    *len = bytes.len();
+    Ok(())
+}
+
+unsafe fn reference<'a, T>(handle: *const T) -> Result<&'a T, NmcStatus> {
+    handle.as_ref().ok_or(NmcStatus::NullPointer)
+}
+
+unsafe fn hand_out<T>(value: T, out: *mut *mut T) -> Result<(), NmcStatus> {
+    if out.is_null() {
+        return Err(NmcStatus::NullPointer);
+    }
+    *out = Box::into_raw(Box::new(value));
+    Ok(())
+}
+
+/// The group nmc-server uses for `bits` = 1024 (RFC 5114), or the RFC 7919
+/// group for 2048, 3072 and 4096
+///
+/// # Safety
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_parameters_standard(
+    bits: usize,
+    out: *mut *mut NmcParameters,
+) -> NmcStatus {
+    guard(|| {
+        let (alpha, beta, p, q) = match bits {
+            1024 => ChaumPedersen::get_constants(),
+            2048 => groups::ffdhe2048(),
+            3072 => groups::ffdhe3072(),
+            4096 => groups::ffdhe4096(),
+            _ => return Err(NmcStatus::InvalidParameters),
+        };
+        hand_out(NmcParameters(ChaumPedersen { alpha, beta, p, q }), out)
+    })
+}
+
+/// Custom parameters; alpha and beta must lie in the order-q subgroup
+///
+/// # Safety
+/// Each buffer must be readable for its length and `out` valid for writes.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn nmc_parameters_new(
+    p: *const u8,
+    p_len: usize,
+    q: *const u8,
+    q_len: usize,
+    alpha: *const u8,
+    alpha_len: usize,
+    beta: *const u8,
+    beta_len: usize,
+    out: *mut *mut NmcParameters,
+) -> NmcStatus {
+    guard(|| {
+        let cp = ChaumPedersen::new(
+            read(p, p_len)?,
+            read(q, q_len)?,
+            read(alpha, alpha_len)?,
+            read(beta, beta_len)?,
+        )
+        .map_err(|_| NmcStatus::InvalidParameters)?;
+        hand_out(NmcParameters(cp), out)
+    })
+}
+
+/// # Safety
+/// `params` must come from `nmc_parameters_*` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_parameters_free(params: *mut NmcParameters) {
+    if !params.is_null() {
+        drop(Box::from_raw(params));
+    }
+}
+
+/// Bytes needed for any element or scalar of the group, i.e. the size of p
+///
+/// # Safety
+/// `params` must be a live handle or null, which gives 0.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_element_len(params: *const NmcParameters) -> usize {
+    match params.as_ref() {
+        Some(params) => params.0.element_len(),
+        None => 0,
+    }
+}
+
+/// (y1, y2) = (alpha^x, beta^x) to register, or (r1, r2) for a nonce x
+///
+/// # Safety
+/// Input buffers must be readable for their length, each output buffer
+/// writable for the capacity in its length pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_compute_pair(
+    params: *const NmcParameters,
+    x: *const u8,
+    x_len: usize,
+    y1: *mut u8,
+    y1_len: *mut usize,
+    y2: *mut u8,
+    y2_len: *mut usize,
+) -> NmcStatus {
+    guard(|| {
+        let cp = &reference(params)?.0;
+        let (first, second) = cp.compute_pair(&read(x, x_len)?);
+        write(&first, y1, y1_len)?;
+        write(&second, y2, y2_len)
+    })
+}
+
+/// A uniform scalar below q, for a nonce k or a challenge c
+///
+/// # Safety
+/// `out` must be writable for `*len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_random_scalar(
+    params: *const NmcParameters,
+    out: *mut u8,
+    len: *mut usize,
+) -> NmcStatus {
+    guard(|| {
+        let cp = &reference(params)?.0;
+        write(&ChaumPedersen::generate_random_below(&cp.q), out, len)
+    })
+}
+
+/// s = k - c * x mod q
+///
+/// # Safety
+/// Input buffers must be readable for their length, `s` writable for `*s_len`.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn nmc_solve(
+    params: *const NmcParameters,
+    k: *const u8,
+    k_len: usize,
+    c: *const u8,
+    c_len: usize,
+    x: *const u8,
+    x_len: usize,
+    s: *mut u8,
+    s_len: *mut usize,
+) -> NmcStatus {
+    guard(|| {
+        let cp = &reference(params)?.0;
+        let value = cp.solve(&read(k, k_len)?, &read(c, c_len)?, &read(x, x_len)?);
+        write(&value, s, s_len)
+    })
+}
+
+/// Ok when r1 == alpha^s * y1^c and r2 == beta^s * y2^c, Invalid otherwise
+///
+/// # Safety
+/// Every buffer must be readable for its length.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn nmc_verify(
+    params: *const NmcParameters,
+    y1: *const u8,
+    y1_len: usize,
+    y2: *const u8,
+    y2_len: usize,
+    r1: *const u8,
+    r1_len: usize,
+    r2: *const u8,
+    r2_len: usize,
+    c: *const u8,
+    c_len: usize,
+    s: *const u8,
+    s_len: usize,
+) -> NmcStatus {
+    guard(|| {
+        let cp = &reference(params)?.0;
+        let valid = cp.verify(
+            &read(y1, y1_len)?,
+            &read(y2, y2_len)?,
+            &read(r1, r1_len)?,
+            &read(r2, r2_len)?,
+            &read(c, c_len)?,
+            &read(s, s_len)?,
+        );
+        valid.then_some(()).ok_or(NmcStatus::Invalid)
+    })
+}
+
+/// Non-interactive proof of knowledge of x
+///
+/// # Safety
+/// `x` must be readable for `x_len` bytes and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_prove(
+    params: *const NmcParameters,
+    x: *const u8,
+    x_len: usize,
+    out: *mut *mut NmcProof,
+) -> NmcStatus {
+    guard(|| {
+        let cp = &reference(params)?.0;
+        hand_out(NmcProof(cp.prove(&read(x, x_len)?)), out)
+    })
+}
+
+/// A proof from its (r1, r2, s) bytes, as received from a prover
+///
+/// # Safety
+/// Each buffer must be readable for its length and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_proof_new(
+    r1: *const u8,
+    r1_len: usize,
+    r2: *const u8,
+    r2_len: usize,
+    s: *const u8,
+    s_len: usize,
+    out: *mut *mut NmcProof,
+) -> NmcStatus {
+    guard(|| {
+        let proof = Proof {
+            r1: read(r1, r1_len)?,
+            r2: read(r2, r2_len)?,
+            s: read(s, s_len)?,
+        };
+        hand_out(NmcProof(proof), out)
+    })
+}
+
+/// Writes the proof's (r1, r2, s) bytes
+///
+/// # Safety
+/// Each output buffer must be writable for the capacity in its length pointer.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_proof_parts(
+    proof: *const NmcProof,
+    r1: *mut u8,
+    r1_len: *mut usize,
+    r2: *mut u8,
+    r2_len: *mut usize,
+    s: *mut u8,
+    s_len: *mut usize,
+) -> NmcStatus {
+    guard(|| {
+        let proof = &reference(proof)?.0;
+        write(&proof.r1, r1, r1_len)?;
+        write(&proof.r2, r2, r2_len)?;
+        write(&proof.s, s, s_len)
+    })
+}
+
+/// Ok when `proof` shows log_alpha(y1) == log_beta(y2), Invalid otherwise
+///
+/// # Safety
+/// `y1` and `y2` must be readable for their length.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_verify_proof(
+    params: *const NmcParameters,
+    y1: *const u8,
+    y1_len: usize,
+    y2: *const u8,
+    y2_len: usize,
+    proof: *const NmcProof,
+) -> NmcStatus {
+    guard(|| {
+        let cp = &reference(params)?.0;
+        let proof = &reference(proof)?.0;
+        let valid = cp.verify_proof(&read(y1, y1_len)?, &read(y2, y2_len)?, proof);
+        valid.then_some(()).ok_or(NmcStatus::Invalid)
+    })
+}
+
+/// # Safety
+/// `proof` must come from `nmc_prove` or `nmc_proof_new` and not be used
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn nmc_proof_free(proof: *mut NmcProof) {
+    if !proof.is_null() {
+        drop(Box::from_raw(proof));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_register_prove_verify() {
+        unsafe {
+            let mut params = ptr::null_mut();
+            assert_eq!(nmc_parameters_standard(1024, &mut params), NmcStatus::Ok);
+            let len = nmc_element_len(params);
+            assert_eq!(len, 128);
+
+            let x = b"password";
+            let (mut y1, mut y2) = (vec![0u8; len], vec![0u8; len]);
+            let (mut y1_len, mut y2_len) = (len, len);
+            let status = nmc_compute_pair(
+                params,
+                x.as_ptr(),
+                x.len(),
+                y1.as_mut_ptr(),
+                &mut y1_len,
+                y2.as_mut_ptr(),
+                &mut y2_len,
+            );
+            assert_eq!(status, NmcStatus::Ok);
+
+            let mut proof = ptr::null_mut();
+            assert_eq!(
+                nmc_prove(params, x.as_ptr(), x.len(), &mut proof),
+                NmcStatus::Ok
+            );
+            let status = nmc_verify_proof(params, y1.as_ptr(), y1_len, y2.as_ptr(), y2_len, proof);
+            assert_eq!(status, NmcStatus::Ok);
+
+            // y1 and y2 swapped do not verify
+            let status = nmc_verify_proof(params, y2.as_ptr(), y2_len, y1.as_ptr(), y1_len, proof);
+            assert_eq!(status, NmcStatus::Invalid);
+
+            nmc_proof_free(proof);
+            nmc_parameters_free(params);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            let mut params = ptr::null_mut();
+            assert_eq!(
+                nmc_parameters_standard(512, &mut params),
+                NmcStatus::InvalidParameters
+            );
+            assert_eq!(
+                nmc_parameters_standard(1024, ptr::null_mut()),
+                NmcStatus::NullPointer
+            );
+            assert_eq!(nmc_element_len(ptr::null()), 0);
+
+            nmc_parameters_standard(1024, &mut params);
+            let mut small = [0u8; 4];
+            let mut len = small.len();
+            assert_eq!(
+                nmc_random_scalar(params, small.as_mut_ptr(), &mut len),
+                NmcStatus::BufferTooSmall
+            );
+
+            let mut proof = ptr::null_mut();
+            assert_eq!(
+                nmc_prove(params, ptr::null(), 0, &mut proof),
+                NmcStatus::NullPointer
+            );
+            assert!(proof.is_null());
+
+            nmc_parameters_free(params);
+        }
+    }
+}
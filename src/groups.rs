@@ -0,0 +1,139 @@
+//! Finite-field groups from RFC 7919 (ffdhe), for parameters beyond the
+//! 1024-bit RFC 5114 group returned by `ChaumPedersen::get_constants`.
+//!
+//! Each p is a safe prime, so q = (p - 1) / 2 and the generator 2 has order
+//! q. Every function returns (alpha, beta, p, q) in the order
+//! `get_constants` uses, with beta = alpha^i for the same fixed i.
+//!
+//! `GroupId` numbers the groups for the `group_id` field of the proto.
+
+use crate::{ChaumPedersen, Error};
+use hex_literal::hex;
+use num_bigint::BigUint;
+
+const FFDHE2048_P: [u8; 256] = hex!("FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B423861285C97FFFFFFFFFFFFFFFF");
+
+const FFDHE3072_P: [u8; 384] = hex!("FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035BBC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91CAEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B66C62E37FFFFFFFFFFFFFFFF");
+
+const FFDHE4096_P: [u8; 512] = hex!("FFFFFFFFFFFFFFFFADF85458A2BB4A9AAFDC5620273D3CF1D8B9C583CE2D3695A9E13641146433FBCC939DCE249B3EF97D2FE363630C75D8F681B202AEC4617AD3DF1ED5D5FD65612433F51F5F066ED0856365553DED1AF3B557135E7F57C935984F0C70E0E68B77E2A689DAF3EFE8721DF158A136ADE73530ACCA4F483A797ABC0AB182B324FB61D108A94BB2C8E3FBB96ADAB760D7F4681D4F42A3DE394DF4AE56EDE76372BB190B07A7C8EE0A6D709E02FCE1CDF7E2ECC03404CD28342F619172FE9CE98583FF8E4F1232EEF28183C3FE3B1B4C6FAD733BB5FCBC2EC22005C58EF1837D1683B2C6F34A26C1B2EFFA886B4238611FCFDCDE355B3B6519035BBC34F4DEF99C023861B46FC9D6E6C9077AD91D2691F7F7EE598CB0FAC186D91CAEFE130985139270B4130C93BC437944F4FD4452E2D74DD364F2E21E71F54BFF5CAE82AB9C9DF69EE86D2BC522363A0DABC521979B0DEADA1DBF9A42D5C4484E0ABCD06BFA53DDEF3C1B20EE3FD59D7C25E41D2B669E1EF16E6F52C3164DF4FB7930E9E4E58857B6AC7D5F42D69F6D187763CF1D5503400487F55BA57E31CC7A7135C886EFB4318AED6A1E012D9E6832A907600A918130C46DC778F971AD0038092999A333CB8B7A1A1DB93D7140003C2A4ECEA9F98D0ACC0A8291CDCEC97DCF8EC9B55A7F88A46B4DB5A851F44182E1C68A007E5E655F6AFFFFFFFFFFFFFFFF");
+
+fn safe_prime_group(p: &[u8]) -> (BigUint, BigUint, BigUint, BigUint) {
+    let p = BigUint::from_bytes_be(p);
+    let q = (&p - 1u32) >> 1;
+    let alpha = BigUint::from(2u32);
+
+    // beta = alpha^i is also a generator
+    let exp = BigUint::from_bytes_be(&hex!("266FEA1E5C41564B777E69"));
+    let beta = alpha.modpow(&exp, &p);
+
+    (alpha, beta, p, q)
+}
+
+pub fn ffdhe2048() -> (BigUint, BigUint, BigUint, BigUint) {
+    safe_prime_group(&FFDHE2048_P)
+}
+
+pub fn ffdhe3072() -> (BigUint, BigUint, BigUint, BigUint) {
+    safe_prime_group(&FFDHE3072_P)
+}
+
+pub fn ffdhe4096() -> (BigUint, BigUint, BigUint, BigUint) {
+    safe_prime_group(&FFDHE4096_P)
+}
+
+/// A group by number, ordered from weakest to strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GroupId {
+    /// The 1024-bit RFC 5114 group from `ChaumPedersen::get_constants`
+    Rfc5114 = 1,
+    Ffdhe2048 = 2,
+    Ffdhe3072 = 3,
+    Ffdhe4096 = 4,
+}
+
+impl GroupId {
+    pub const ALL: [GroupId; 4] = [
+        GroupId::Rfc5114,
+        GroupId::Ffdhe2048,
+        GroupId::Ffdhe3072,
+        GroupId::Ffdhe4096,
+    ];
+
+    pub fn parameters(self) -> ChaumPedersen {
+        let (alpha, beta, p, q) = match self {
+            GroupId::Rfc5114 => ChaumPedersen::get_constants(),
+            GroupId::Ffdhe2048 => ffdhe2048(),
+            GroupId::Ffdhe3072 => ffdhe3072(),
+            GroupId::Ffdhe4096 => ffdhe4096(),
+        };
+        ChaumPedersen { alpha, beta, p, q }
+    }
+}
+
+impl From<GroupId> for u32 {
+    fn from(id: GroupId) -> u32 {
+        id as u32
+    }
+}
+
+/// 0, proto3's default, is never a group, so a missing `group_id` is rejected
+impl TryFrom<u32> for GroupId {
+    type Error = Error;
+
+    fn try_from(id: u32) -> Result<Self, Error> {
+        GroupId::ALL
+            .into_iter()
+            .find(|group| *group as u32 == id)
+            .ok_or_else(|| Error::InvalidParameters(alloc::format!("unknown group_id {}", id)))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ffdhe_groups() {
+        for (bits, (alpha, beta, p, q)) in [
+            (2048, ffdhe2048()),
+            (3072, ffdhe3072()),
+            (4096, ffdhe4096()),
+        ] {
+            assert_eq!(p.bits(), bits);
+            assert_eq!(&q * 2u32 + 1u32, p);
+
+            let cp = ChaumPedersen { alpha, beta, p, q };
+            assert!(cp.is_element(&cp.alpha));
+            assert!(cp.is_element(&cp.beta));
+
+            let x = ChaumPedersen::generate_random_below(&cp.q);
+            let (y1, y2) = cp.compute_pair(&x);
+            assert!(cp.verify_proof(&y1, &y2, &cp.prove(&x)));
+        }
+    }
+
+    #[test]
+    fn test_group_ids() {
+        for id in GroupId::ALL {
+            assert_eq!(GroupId::try_from(u32::from(id)), Ok(id));
+        }
+        assert!(GroupId::try_from(0).is_err());
+        assert!(GroupId::try_from(5).is_err());
+
+        // stronger groups sort later
+        let bits: Vec<u64> = GroupId::ALL
+            .iter()
+            .map(|id| id.parameters().p.bits())
+            .collect();
+        assert_eq!(bits, [1024, 2048, 3072, 4096]);
+
+        // every group has its own fingerprint
+        let mut fingerprints: Vec<Vec<u8>> = GroupId::ALL
+            .iter()
+            .map(|id| id.parameters().fingerprint())
+            .collect();
+        fingerprints.sort();
+        fingerprints.dedup();
+        assert_eq!(fingerprints.len(), GroupId::ALL.len());
+    }
+}
@@ -0,0 +1,145 @@
+//! Deriving the secret x from a password.
+//!
+//! The server records each user's `Kdf` at registration and hands it back
+//! before login, so the client can derive the same x again. `Raw` is how
+//! `nmc-client` has always derived x and is kept for existing users; new
+//! registrations use `Pbkdf2Sha256` with a fresh salt.
+//!
+//! x does not depend on the group: it is an integer that exponentiation
+//! reduces mod whichever q it is used with, so the same password and `Kdf`
+//! give the same x in every group and a record can move between groups.
+
+use crate::{Error, Result};
+use alloc::{format, vec, vec::Vec};
+use num_bigint::BigUint;
+use rand::{CryptoRng, RngCore};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kdf {
+    /// x = the password's bytes as a big-endian integer
+    Raw,
+    /// x = the `OUTPUT_LEN`-byte PBKDF2-HMAC-SHA256(password, salt,
+    /// iterations) as a big-endian integer, wide enough that its reduction
+    /// mod any supported q is near-uniform
+    Pbkdf2Sha256 { salt: Vec<u8>, iterations: u32 },
+}
+
+impl Kdf {
+    pub const MIN_ITERATIONS: u32 = 10_000;
+    pub const DEFAULT_ITERATIONS: u32 = 100_000;
+    pub const SALT_LEN: usize = 16;
+    /// Bytes of PBKDF2 output in x, so x < 2^(8 * OUTPUT_LEN)
+    pub const OUTPUT_LEN: usize = 64;
+
+    /// PBKDF2 with a random salt and `DEFAULT_ITERATIONS`
+    #[cfg(feature = "std")]
+    pub fn pbkdf2_sha256() -> Self {
+        Self::pbkdf2_sha256_with(&mut rand::thread_rng())
+    }
+
+    /// `pbkdf2_sha256` with the salt drawn from `rng`
+    pub fn pbkdf2_sha256_with<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut salt = vec![0u8; Self::SALT_LEN];
+        rng.fill_bytes(&mut salt);
+
+        Kdf::Pbkdf2Sha256 {
+            salt,
+            iterations: Self::DEFAULT_ITERATIONS,
+        }
+    }
+
+    /// Rejects PBKDF2 settings weaker than `MIN_ITERATIONS` and `SALT_LEN`
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Kdf::Raw => Ok(()),
+            Kdf::Pbkdf2Sha256 { salt, iterations } => {
+                if *iterations < Self::MIN_ITERATIONS {
+                    return Err(Error::InvalidParameters(format!(
+                        "{} PBKDF2 iterations, at least {} needed",
+                        iterations,
+                        Self::MIN_ITERATIONS
+                    )));
+                }
+                if salt.len() < Self::SALT_LEN {
+                    return Err(Error::InvalidParameters(format!(
+                        "{}-byte PBKDF2 salt, at least {} needed",
+                        salt.len(),
+                        Self::SALT_LEN
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn derive(&self, password: &[u8]) -> BigUint {
+        match self {
+            Kdf::Raw => BigUint::from_bytes_be(password),
+            Kdf::Pbkdf2Sha256 { salt, iterations } => {
+                let mut output = vec![0u8; Self::OUTPUT_LEN];
+                pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password, salt, *iterations, &mut output);
+                BigUint::from_bytes_be(&output)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::groups::GroupId;
+
+    #[test]
+    fn test_derive() {
+        assert_eq!(
+            Kdf::Raw.derive(b"password"),
+            BigUint::from_bytes_be(b"password")
+        );
+
+        let kdf = Kdf::Pbkdf2Sha256 {
+            salt: vec![7; Kdf::SALT_LEN],
+            iterations: Kdf::MIN_ITERATIONS,
+        };
+        let x = kdf.derive(b"password");
+        assert!(x.bits() <= 8 * Kdf::OUTPUT_LEN as u64);
+        assert_eq!(kdf.derive(b"password"), x);
+        assert_ne!(kdf.derive(b"passw0rd"), x);
+
+        // the salt separates users with the same password
+        let other = Kdf::Pbkdf2Sha256 {
+            salt: vec![8; Kdf::SALT_LEN],
+            iterations: Kdf::MIN_ITERATIONS,
+        };
+        assert_ne!(other.derive(b"password"), x);
+
+        // the same x logs in where q is narrower than x and where it is wider
+        for id in [GroupId::Rfc5114, GroupId::Ffdhe2048] {
+            let cp = id.parameters();
+            let (y1, y2) = cp.compute_pair(&x);
+            let proof = cp.prove(&x);
+            assert!(cp.verify_proof(&y1, &y2, &proof));
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Kdf::Raw.validate().is_ok());
+        assert!(Kdf::pbkdf2_sha256().validate().is_ok());
+        assert_ne!(Kdf::pbkdf2_sha256(), Kdf::pbkdf2_sha256());
+
+        let weak = Kdf::Pbkdf2Sha256 {
+            salt: vec![0; Kdf::SALT_LEN],
+            iterations: 1,
+        };
+        assert!(matches!(weak.validate(), Err(Error::InvalidParameters(_))));
+
+        let unsalted = Kdf::Pbkdf2Sha256 {
+            salt: Vec::new(),
+            iterations: Kdf::DEFAULT_ITERATIONS,
+        };
+        assert!(matches!(
+            unsalted.validate(),
+            Err(Error::InvalidParameters(_))
+        ));
+    }
+}
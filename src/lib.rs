@@ -1,61 +1,357 @@
-use hex;
+//! The core (`ChaumPedersen`, `backend`, `groups` and `transcript`) builds
+//! without `std`, drawing randomness from an injected `RngCore`. The `std`
+//! feature, on by default, adds the `thread_rng` conveniences and the
+//! protocols built on them.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{format, vec, vec::Vec};
+use backend::Backend;
+use hex_literal::hex;
 use num_bigint::{BigUint, RandBigInt};
-use rand::Rng;
+use rand::{CryptoRng, RngCore};
+use transcript::{hash_to_scalar, hash_to_subgroup, HashFunction};
+
+pub mod backend;
+#[cfg(feature = "std")]
+pub mod blind;
+#[cfg(feature = "constant-time")]
+mod ct;
+#[cfg(feature = "std")]
+pub mod designated;
+#[cfg(feature = "std")]
+pub mod dkg;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod groups;
+pub mod kdf;
+#[cfg(feature = "std")]
+pub mod linear;
+#[cfg(feature = "std")]
+pub mod migrate;
+#[cfg(feature = "std")]
+pub mod poly;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+pub mod ring;
+#[cfg(feature = "std")]
+pub mod schnorr;
+#[cfg(feature = "serde")]
+pub mod serialize;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod sigma;
+#[cfg(feature = "std")]
+pub mod threshold;
+pub mod transcript;
+#[cfg(feature = "std")]
+pub mod undeniable;
+#[cfg(feature = "std")]
+pub mod vss;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::{Error, Result};
+
+/// Group parameters, over `BigUint` unless another `Backend` is chosen.
+/// Deserializing runs the same checks as `ChaumPedersen::new`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "I: Backend", try_from = "serialize::UncheckedParameters<I>")
+)]
+pub struct ChaumPedersen<I = BigUint> {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub p: I,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub q: I,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub alpha: I,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub beta: I,
+}
 
-pub struct ChaumPedersen {
-    pub p: BigUint,
-    pub q: BigUint,
-    pub alpha: BigUint,
-    pub beta: BigUint,
+/// (y1, y2) = (alpha^x, beta^x), what a user registers
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PublicKey {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub y1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub y2: BigUint,
 }
 
-impl ChaumPedersen {
+/// (r1, r2) = (alpha^k, beta^k), the prover's first message
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Commitment {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub r1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub r2: BigUint,
+}
+
+/// Every message of one interactive run, checkable later with `verify`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InteractiveTranscript {
+    pub public_key: PublicKey,
+    pub commitment: Commitment,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub c: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub s: BigUint,
+}
+
+/// Non-interactive (Fiat-Shamir) proof that log_alpha(y1) == log_beta(y2)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Proof {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub r1: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub r2: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize"))]
+    pub s: BigUint,
+}
+
+impl InteractiveTranscript {
+    pub fn verify(&self, cp: &ChaumPedersen) -> bool {
+        cp.verify(
+            &self.public_key.y1,
+            &self.public_key.y2,
+            &self.commitment.r1,
+            &self.commitment.r2,
+            &self.c,
+            &self.s,
+        )
+    }
+}
+
+impl<I: Backend> ChaumPedersen<I> {
     /// output = (alpha^exp mod p, beta^exp mod p)
-    pub fn compute_pair(&self, exp: &BigUint) -> (BigUint, BigUint) {
-        let p1 = self.alpha.modpow(exp, &self.p);
-        let p2 = self.beta.modpow(exp, &self.p);
-        (p1, p2)
+    pub fn compute_pair(&self, exp: &I) -> (I, I) {
+        I::compute_pair(self, exp)
     }
 
     /// output = s = k - c * x mod q
-    pub fn solve(&self, k: &BigUint, c: &BigUint, x: &BigUint) -> BigUint {
-        if *k >= c * x {
-            return (k - c * x).modpow(&BigUint::from(1u32), &self.q);
-        }
-        &self.q - (c * x - k).modpow(&BigUint::from(1u32), &self.q)
+    pub fn solve(&self, k: &I, c: &I, x: &I) -> I {
+        I::solve(self, k, c, x)
     }
 
     /// r1 = alpha^s * y1^c
     /// r2 = beta^s * y2^c
-    pub fn verify(
-        &self,
-        y1: &BigUint,
-        y2: &BigUint,
-        r1: &BigUint,
-        r2: &BigUint,
-        c: &BigUint,
-        s: &BigUint,
-    ) -> bool {
-        let rhs1 = (&self.alpha.modpow(s, &self.p) * y1.modpow(c, &self.p))
-            .modpow(&BigUint::from(1u32), &self.p);
-        let rhs2 = (&self.beta.modpow(s, &self.p) * y2.modpow(c, &self.p))
-            .modpow(&BigUint::from(1u32), &self.p);
-        let condition1 = *r1
-            == (&self.alpha.modpow(s, &self.p) * y1.modpow(c, &self.p))
-                .modpow(&BigUint::from(1u32), &self.p);
-        let condition2 = *r2
-            == (&self.beta.modpow(s, &self.p) * y2.modpow(c, &self.p))
-                .modpow(&BigUint::from(1u32), &self.p);
+    pub fn verify(&self, y1: &I, y2: &I, r1: &I, r2: &I, c: &I, s: &I) -> bool {
+        let rhs1 = self
+            .alpha
+            .pow_mod(s, &self.p)
+            .mul_mod(&y1.pow_mod(c, &self.p), &self.p);
+        let rhs2 = self
+            .beta
+            .pow_mod(s, &self.p)
+            .mul_mod(&y2.pow_mod(c, &self.p), &self.p);
+        let condition1 = *r1 == rhs1;
+        let condition2 = *r2 == rhs2;
 
         condition1 && condition2
     }
 
+    /// output = true iff 0 < value < p and value^q = 1 mod p
+    pub fn is_element(&self, value: &I) -> bool {
+        *value > I::from_u32(0)
+            && *value < self.p
+            && value.pow_mod(&self.q, &self.p) == I::from_u32(1)
+    }
+
+    /// Checks that p and q are odd and alpha and beta lie in the order-q
+    /// subgroup of Z_p*
+    fn validate(self) -> Result<Self> {
+        let (p, q) = (self.p.to_biguint(), self.q.to_biguint());
+        if !p.bit(0) || !q.bit(0) {
+            return Err(Error::InvalidParameters("p and q must be odd".into()));
+        }
+        #[cfg(feature = "constant-time")]
+        if p.bits() > ct::MAX_BITS {
+            return Err(Error::InvalidParameters(format!(
+                "the constant-time prover handles groups of at most {} bits",
+                ct::MAX_BITS
+            )));
+        }
+        if !self.is_element(&self.alpha) || !self.is_element(&self.beta) {
+            return Err(Error::InvalidParameters(
+                "alpha and beta must lie in the order-q subgroup".into(),
+            ));
+        }
+        Ok(self)
+    }
+}
+
+impl ChaumPedersen {
+    /// Checks that alpha and beta lie in the order-q subgroup of Z_p*
+    pub fn new(p: BigUint, q: BigUint, alpha: BigUint, beta: BigUint) -> Result<Self> {
+        ChaumPedersen { p, q, alpha, beta }.validate()
+    }
+
+    /// Length of every group element on the wire: the byte length of p
+    pub fn element_len(&self) -> usize {
+        self.p.bits().div_ceil(8) as usize
+    }
+
+    /// Length of every scalar on the wire: the byte length of q
+    pub fn scalar_len(&self) -> usize {
+        self.q.bits().div_ceil(8) as usize
+    }
+
+    /// value as exactly `element_len()` big-endian bytes
+    pub fn encode_element(&self, value: &BigUint) -> Vec<u8> {
+        to_fixed_bytes(value, self.element_len())
+    }
+
+    /// value as exactly `scalar_len()` big-endian bytes
+    pub fn encode_scalar(&self, value: &BigUint) -> Vec<u8> {
+        to_fixed_bytes(value, self.scalar_len())
+    }
+
+    /// The inverse of `encode_element`, rejecting any other length and any
+    /// value outside the order-q subgroup
+    pub fn decode_element(&self, bytes: &[u8]) -> Result<BigUint> {
+        if bytes.len() != self.element_len() {
+            return Err(Error::Decode(format!(
+                "element is {} bytes, expected {}",
+                bytes.len(),
+                self.element_len()
+            )));
+        }
+        let value = BigUint::from_bytes_be(bytes);
+        if !self.is_element(&value) {
+            return Err(Error::Decode(format!(
+                "{} is not an element of the order-q subgroup",
+                value
+            )));
+        }
+        Ok(value)
+    }
+
+    /// The inverse of `encode_scalar`, rejecting any other length and q or above
+    pub fn decode_scalar(&self, bytes: &[u8]) -> Result<BigUint> {
+        if bytes.len() != self.scalar_len() {
+            return Err(Error::Decode(format!(
+                "scalar is {} bytes, expected {}",
+                bytes.len(),
+                self.scalar_len()
+            )));
+        }
+        let value = BigUint::from_bytes_be(bytes);
+        if value >= self.q {
+            return Err(Error::Decode(format!("{} is not below q", value)));
+        }
+        Ok(value)
+    }
+
+    /// The same group over another backend; fails if it does not fit
+    pub fn with_backend<I: Backend>(&self) -> Result<ChaumPedersen<I>> {
+        let convert = |value: &BigUint| {
+            I::from_biguint(value).ok_or_else(|| {
+                Error::InvalidParameters(format!(
+                    "a {}-bit group does not fit the backend",
+                    self.p.bits()
+                ))
+            })
+        };
+        Ok(ChaumPedersen {
+            p: convert(&self.p)?,
+            q: convert(&self.q)?,
+            alpha: convert(&self.alpha)?,
+            beta: convert(&self.beta)?,
+        })
+    }
+
+    /// SHA-256 of (p, q, alpha, beta) under the "parameters" tag, so two
+    /// parties can check they run the same group
+    pub fn fingerprint(&self) -> Vec<u8> {
+        transcript::expand(
+            HashFunction::Sha256,
+            b"parameters",
+            &[
+                &self.p.to_bytes_be(),
+                &self.q.to_bytes_be(),
+                &self.alpha.to_bytes_be(),
+                &self.beta.to_bytes_be(),
+            ],
+            32,
+        )
+    }
+
+    /// c = H(items) mod q, SHA-256 under the "challenge" tag
+    pub fn hash_to_challenge(&self, items: &[&[u8]]) -> BigUint {
+        hash_to_scalar(HashFunction::Sha256, b"challenge", items, &self.q)
+    }
+
+    /// h = H(items)^((p-1)/q) mod p, an element of the order-q subgroup whose
+    /// discrete log relative to alpha is unknown
+    pub fn hash_to_group(&self, items: &[&[u8]]) -> BigUint {
+        hash_to_subgroup(HashFunction::Sha256, b"group", items, &self.p, &self.q)
+    }
+
+    /// c = H(p, q, alpha, beta, y1, y2, r1, r2)
+    fn proof_challenge(&self, y1: &BigUint, y2: &BigUint, r1: &BigUint, r2: &BigUint) -> BigUint {
+        self.hash_to_challenge(&[
+            &self.p.to_bytes_be(),
+            &self.q.to_bytes_be(),
+            &self.alpha.to_bytes_be(),
+            &self.beta.to_bytes_be(),
+            &y1.to_bytes_be(),
+            &y2.to_bytes_be(),
+            &r1.to_bytes_be(),
+            &r2.to_bytes_be(),
+        ])
+    }
+
+    /// Non-interactive proof of knowledge of x with (y1, y2) = compute_pair(x)
+    #[cfg(feature = "std")]
+    pub fn prove(&self, x: &BigUint) -> Proof {
+        self.prove_with(&mut rand::thread_rng(), x)
+    }
+
+    /// `prove` with the nonce k drawn from `rng`
+    pub fn prove_with<R: RngCore + CryptoRng>(&self, rng: &mut R, x: &BigUint) -> Proof {
+        let (y1, y2) = self.compute_pair(x);
+        let k = Self::generate_random_below_with(rng, &self.q);
+        let (r1, r2) = self.compute_pair(&k);
+        let c = self.proof_challenge(&y1, &y2, &r1, &r2);
+        let s = self.solve(&k, &c, x);
+
+        Proof { r1, r2, s }
+    }
+
+    /// Recomputes the Fiat-Shamir challenge and runs `verify`
+    pub fn verify_proof(&self, y1: &BigUint, y2: &BigUint, proof: &Proof) -> bool {
+        let c = self.proof_challenge(y1, y2, &proof.r1, &proof.r2);
+        self.verify(y1, y2, &proof.r1, &proof.r2, &c, &proof.s)
+    }
+
+    #[cfg(feature = "std")]
     pub fn generate_random_below(bound: &BigUint) -> BigUint {
-        let mut rng = rand::thread_rng();
+        Self::generate_random_below_with(&mut rand::thread_rng(), bound)
+    }
+
+    pub fn generate_random_below_with<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        bound: &BigUint,
+    ) -> BigUint {
         rng.gen_biguint_below(bound)
     }
 
+    #[cfg(feature = "std")]
     pub fn generate_random_string(size: usize) -> String {
+        use rand::Rng;
+
         rand::thread_rng()
             .sample_iter(rand::distributions::Alphanumeric)
             .take(size)
@@ -64,24 +360,31 @@ impl ChaumPedersen {
     }
 
     pub fn get_constants() -> (BigUint, BigUint, BigUint, BigUint) {
-        let p = BigUint::from_bytes_be(&hex::decode("B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B616073E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BFACCBDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0A151AF5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371").unwrap());
-        let q = BigUint::from_bytes_be(
-            &hex::decode("F518AA8781A8DF278ABA4E7D64B7CB9D49462353").unwrap(),
-        );
+        let p = BigUint::from_bytes_be(&hex!("B10B8F96A080E01DDE92DE5EAE5D54EC52C99FBCFB06A3C69A6A9DCA52D23B616073E28675A23D189838EF1E2EE652C013ECB4AEA906112324975C3CD49B83BFACCBDD7D90C4BD7098488E9C219A73724EFFD6FAE5644738FAA31A4FF55BCCC0A151AF5F0DC8B4BD45BF37DF365C1A65E68CFDA76D4DA708DF1FB2BC2E4A4371"));
+        let q = BigUint::from_bytes_be(&hex!("F518AA8781A8DF278ABA4E7D64B7CB9D49462353"));
 
         let alpha = BigUint::from_bytes_be(
-            &hex::decode("A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31266FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5").unwrap(),
+            &hex!("A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31266FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5"),
         );
 
         // beta = alpha^i is also a generator
-        let exp = BigUint::from_bytes_be(&hex::decode("266FEA1E5C41564B777E69").unwrap());
+        let exp = BigUint::from_bytes_be(&hex!("266FEA1E5C41564B777E69"));
         let beta = alpha.modpow(&exp, &p);
 
         (alpha, beta, p, q)
     }
 }
 
-#[cfg(test)]
+/// Left-pads with zeros; a value longer than len is left as is, and then
+/// fails to decode on the other side
+pub(crate) fn to_fixed_bytes(value: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let mut padded = vec![0u8; len.saturating_sub(bytes.len())];
+    padded.extend_from_slice(&bytes);
+    padded
+}
+
+#[cfg(all(test, feature = "std"))]
 mod test {
     use super::*;
 
@@ -216,4 +519,65 @@ mod test {
         let result = cp.verify(&y1, &y2, &r1, &r2, &c, &s);
         assert!(result);
     }
+
+    #[test]
+    fn test_non_interactive_proof() {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen { alpha, beta, p, q };
+
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, y2) = cp.compute_pair(&x);
+
+        let proof = cp.prove(&x);
+        assert!(cp.verify_proof(&y1, &y2, &proof));
+
+        // proof for another secret does not verify against (y1, y2)
+        let x_fake = ChaumPedersen::generate_random_below(&cp.q);
+        let proof_fake = cp.prove(&x_fake);
+        assert!(!cp.verify_proof(&y1, &y2, &proof_fake));
+    }
+
+    #[test]
+    fn test_prove_with_injected_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen { alpha, beta, p, q };
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let x = ChaumPedersen::generate_random_below_with(&mut rng, &cp.q);
+        let (y1, y2) = cp.compute_pair(&x);
+
+        let proof = cp.prove_with(&mut rng, &x);
+        assert!(cp.verify_proof(&y1, &y2, &proof));
+
+        // the same seed gives the same nonce, and so the same proof
+        let mut rng = StdRng::seed_from_u64(7);
+        ChaumPedersen::generate_random_below_with(&mut rng, &cp.q);
+        assert_eq!(cp.prove_with(&mut rng, &x), proof);
+    }
+
+    #[test]
+    fn test_fixed_length_encoding() {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen { alpha, beta, p, q };
+        assert_eq!((cp.element_len(), cp.scalar_len()), (128, 20));
+
+        // the identity still takes the full 128 bytes
+        let one = BigUint::from(1u32);
+        let encoded = cp.encode_element(&one);
+        assert_eq!(encoded.len(), 128);
+        assert_eq!(cp.decode_element(&encoded), Ok(one.clone()));
+        assert!(cp.decode_element(&one.to_bytes_be()).is_err());
+
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, _) = cp.compute_pair(&x);
+        assert_eq!(cp.decode_element(&cp.encode_element(&y1)), Ok(y1));
+        assert_eq!(cp.decode_scalar(&cp.encode_scalar(&x)), Ok(x));
+
+        // q itself has the right length but is not a scalar
+        assert!(cp.decode_scalar(&cp.encode_scalar(&cp.q)).is_err());
+        assert!(cp.decode_element(&cp.encode_element(&cp.p)).is_err());
+        assert!(cp.decode_scalar(&[0u8; 21]).is_err());
+    }
 }
@@ -0,0 +1,307 @@
+//! Proofs of linear relations between several secrets.
+//!
+//! A statement is a list of group equations over witnesses w_0..w_{n-1}:
+//!
+//!   Y_j = G_{j,0}^{w_i0} * G_{j,1}^{w_i1} * ...
+//!
+//! `ChaumPedersen` is the case of one witness and the two equations
+//! y1 = alpha^x, y2 = beta^x. A relation between the secrets themselves,
+//! such as a + 2b = t mod q, is the equation alpha^t = alpha^a * (alpha^2)^b,
+//! which `LinearRelation::scalar` builds.
+
+use crate::{
+    sigma::{Encode, SigmaProtocol},
+    ChaumPedersen, Error, Result,
+};
+use num_bigint::BigUint;
+
+/// lhs = prod base^w[index] over `terms`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation {
+    pub lhs: BigUint,
+    pub terms: Vec<(BigUint, usize)>,
+}
+
+impl Equation {
+    pub fn new(lhs: BigUint) -> Self {
+        Equation {
+            lhs,
+            terms: Vec::new(),
+        }
+    }
+
+    pub fn term(mut self, base: BigUint, witness: usize) -> Self {
+        self.terms.push((base, witness));
+        self
+    }
+}
+
+impl Encode for Equation {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        self.lhs.encode(out);
+        out.push((self.terms.len() as u64).to_be_bytes().to_vec());
+        for (base, witness) in &self.terms {
+            base.encode(out);
+            out.push((*witness as u64).to_be_bytes().to_vec());
+        }
+    }
+}
+
+/// Proof system for equations over `witnesses` secrets in the order-q subgroup of Z_p^*
+#[derive(Debug, Clone)]
+pub struct LinearRelation {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub witnesses: usize,
+}
+
+impl LinearRelation {
+    pub fn new(cp: &ChaumPedersen, witnesses: usize) -> Self {
+        LinearRelation {
+            p: cp.p.clone(),
+            q: cp.q.clone(),
+            witnesses,
+        }
+    }
+
+    /// sum a_i * w_i = t mod q, as g^t = prod (g^a_i)^w_i
+    pub fn scalar(&self, g: &BigUint, coefficients: &[(BigUint, usize)], t: &BigUint) -> Equation {
+        coefficients
+            .iter()
+            .fold(Equation::new(g.modpow(t, &self.p)), |equation, (a, i)| {
+                equation.term(g.modpow(a, &self.p), *i)
+            })
+    }
+
+    /// prod base^exponent[index] over `terms`
+    fn evaluate(&self, terms: &[(BigUint, usize)], exponents: &[BigUint]) -> BigUint {
+        terms.iter().fold(BigUint::from(1u32), |acc, (base, i)| {
+            (acc * base.modpow(&exponents[*i], &self.p)) % &self.p
+        })
+    }
+
+    fn well_formed(&self, equations: &[Equation]) -> bool {
+        equations
+            .iter()
+            .all(|equation| equation.terms.iter().all(|(_, i)| *i < self.witnesses))
+    }
+
+    fn check(&self, equations: &[Equation]) -> Result<()> {
+        if self.well_formed(equations) {
+            Ok(())
+        } else {
+            Err(Error::InvalidParameters(format!(
+                "equation refers to a witness outside 0..{}",
+                self.witnesses
+            )))
+        }
+    }
+}
+
+impl Encode for LinearRelation {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        self.p.encode(out);
+        self.q.encode(out);
+        out.push((self.witnesses as u64).to_be_bytes().to_vec());
+    }
+}
+
+impl SigmaProtocol for LinearRelation {
+    type Statement = Vec<Equation>;
+    type Witness = Vec<BigUint>;
+    /// R_j = prod G_j^k, one per equation
+    type Commitment = Vec<BigUint>;
+    /// s_i = k_i - c * w_i mod q, one per witness
+    type Response = Vec<BigUint>;
+    type State = Vec<BigUint>;
+
+    fn challenge_modulus(&self) -> &BigUint {
+        &self.q
+    }
+
+    fn commit(
+        &self,
+        equations: &Vec<Equation>,
+        _: &Vec<BigUint>,
+    ) -> Result<(Vec<BigUint>, Vec<BigUint>)> {
+        self.check(equations)?;
+        let k: Vec<BigUint> = (0..self.witnesses)
+            .map(|_| ChaumPedersen::generate_random_below(&self.q))
+            .collect();
+        let commitment = equations
+            .iter()
+            .map(|equation| self.evaluate(&equation.terms, &k))
+            .collect();
+        Ok((k, commitment))
+    }
+
+    fn respond(
+        &self,
+        _: &Vec<Equation>,
+        w: &Vec<BigUint>,
+        k: Vec<BigUint>,
+        c: &BigUint,
+    ) -> Result<Vec<BigUint>> {
+        if w.len() != self.witnesses || k.len() != self.witnesses {
+            return Err(Error::InvalidParameters(format!(
+                "{} witnesses and {} nonces for a relation over {}",
+                w.len(),
+                k.len(),
+                self.witnesses
+            )));
+        }
+        Ok(k.iter()
+            .zip(w)
+            .map(|(k, w)| {
+                let cw = (c * w) % &self.q;
+                (k + &self.q - cw) % &self.q
+            })
+            .collect())
+    }
+
+    /// R_j == prod G_j^s * Y_j^c for every equation
+    fn verify(
+        &self,
+        equations: &Vec<Equation>,
+        commitment: &Vec<BigUint>,
+        c: &BigUint,
+        s: &Vec<BigUint>,
+    ) -> bool {
+        self.well_formed(equations)
+            && commitment.len() == equations.len()
+            && s.len() == self.witnesses
+            && equations.iter().zip(commitment).all(|(equation, r)| {
+                *r == (self.evaluate(&equation.terms, s) * equation.lhs.modpow(c, &self.p))
+                    % &self.p
+            })
+    }
+
+    fn simulate(
+        &self,
+        equations: &Vec<Equation>,
+        c: &BigUint,
+    ) -> Result<(Vec<BigUint>, Vec<BigUint>)> {
+        self.check(equations)?;
+        let s: Vec<BigUint> = (0..self.witnesses)
+            .map(|_| ChaumPedersen::generate_random_below(&self.q))
+            .collect();
+        let commitment = equations
+            .iter()
+            .map(|equation| {
+                (self.evaluate(&equation.terms, &s) * equation.lhs.modpow(c, &self.p)) % &self.p
+            })
+            .collect();
+        Ok((commitment, s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sigma::FiatShamir;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    #[test]
+    fn test_chaum_pedersen_as_linear_relation() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, y2) = cp.compute_pair(&x);
+
+        let relation = LinearRelation::new(&cp, 1);
+        let statement = vec![
+            Equation::new(y1).term(cp.alpha.clone(), 0),
+            Equation::new(y2).term(cp.beta.clone(), 0),
+        ];
+
+        let (k, commitment) = relation.commit(&statement, &vec![x.clone()]).unwrap();
+        let c = relation.challenge();
+        let s = relation
+            .respond(&statement, &vec![x.clone()], k, &c)
+            .unwrap();
+        assert!(relation.verify(&statement, &commitment, &c, &s));
+
+        // the single response is exactly a Chaum-Pedersen one
+        assert!(cp.verify(
+            &statement[0].lhs,
+            &statement[1].lhs,
+            &commitment[0],
+            &commitment[1],
+            &c,
+            &s[0]
+        ));
+    }
+
+    #[test]
+    fn test_two_secrets_with_scalar_relation() {
+        let cp = constants();
+        let relation = LinearRelation::new(&cp, 2);
+
+        // y1 = alpha^a, y2 = beta^b, a + 2b = t mod q
+        let a = ChaumPedersen::generate_random_below(&cp.q);
+        let b = ChaumPedersen::generate_random_below(&cp.q);
+        let t = (&a + BigUint::from(2u32) * &b) % &cp.q;
+
+        let statement = |t: &BigUint| {
+            vec![
+                Equation::new(cp.alpha.modpow(&a, &cp.p)).term(cp.alpha.clone(), 0),
+                Equation::new(cp.beta.modpow(&b, &cp.p)).term(cp.beta.clone(), 1),
+                relation.scalar(
+                    &cp.alpha,
+                    &[(BigUint::from(1u32), 0), (BigUint::from(2u32), 1)],
+                    t,
+                ),
+            ]
+        };
+
+        let proof_system = FiatShamir(relation.clone());
+        let witness = vec![a.clone(), b.clone()];
+        let proof = proof_system
+            .prove(&statement(&t), &witness, b"context")
+            .unwrap();
+
+        assert!(proof_system.verify(&statement(&t), b"context", &proof));
+        assert!(!proof_system.verify(&statement(&t), b"other", &proof));
+        assert!(!proof_system.verify(&statement(&(&t + 1u32)), b"context", &proof));
+
+        // the relation does not hold for t + 1, so no witness can prove it
+        let proof = proof_system
+            .prove(&statement(&(&t + 1u32)), &witness, b"context")
+            .unwrap();
+        assert!(!proof_system.verify(&statement(&(&t + 1u32)), b"context", &proof));
+
+        let c = relation.challenge();
+        let (commitment, s) = relation.simulate(&statement(&t), &c).unwrap();
+        assert!(relation.verify(&statement(&t), &commitment, &c, &s));
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, _) = cp.compute_pair(&x);
+        let relation = LinearRelation::new(&cp, 1);
+
+        // witness index 1 in a relation over one witness
+        let statement = vec![Equation::new(y1.clone()).term(cp.alpha.clone(), 1)];
+        let c = relation.challenge();
+        assert!(matches!(
+            relation.commit(&statement, &vec![x.clone()]),
+            Err(Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            relation.simulate(&statement, &c),
+            Err(Error::InvalidParameters(_))
+        ));
+
+        let statement = vec![Equation::new(y1).term(cp.alpha.clone(), 0)];
+        let (k, _) = relation.commit(&statement, &vec![x.clone()]).unwrap();
+        assert!(matches!(
+            relation.respond(&statement, &vec![], k, &c),
+            Err(Error::InvalidParameters(_))
+        ));
+    }
+}
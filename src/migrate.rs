@@ -0,0 +1,354 @@
+//! Moving a key pair to another group.
+//!
+//! A proof that (y1, y2) in one group and (y1', y2') in another share the
+//! same secret x, so a server can swap a user's record for the new pair
+//! without a fresh registration. The two groups have different orders, so
+//! there is no single Z_q to answer in: each response s = k + c * x is an
+//! integer, with k wide enough that s hides x.
+//!
+//! A wide challenge would let a prover answer for x = a / b whenever b
+//! divides c, or combine one answer mod each q by the CRT. So the proof
+//! runs `ROUNDS` rounds with a one-bit challenge each, and every response
+//! must fit in `RESPONSE_BITS`: two answers to one commitment give
+//! x = s1 - s0, a single integer below 2^RESPONSE_BITS behind both pairs.
+//! Every supported group moved to has q wider than that, so the new pair
+//! pins x down and the old one must be the same x.
+
+use crate::{
+    kdf::Kdf,
+    to_fixed_bytes,
+    transcript::{HashFunction, Transcript},
+    ChaumPedersen, Error, Result,
+};
+use num_bigint::BigUint;
+
+/// Rounds of the proof; a prover without x passes each with probability 1/2
+pub const ROUNDS: usize = 128;
+/// Bits in the largest x that can be moved, a PBKDF2 x or a raw password of
+/// up to as many bytes
+pub const SECRET_BITS: u64 = 8 * Kdf::OUTPUT_LEN as u64;
+/// Bits k exceeds x by, the statistical hiding of x in s
+const MASK_BITS: u64 = 128;
+/// Bits in a valid response k + c * x
+pub const RESPONSE_BITS: u64 = SECRET_BITS + MASK_BITS + 1;
+/// Length of every response on the wire
+pub const RESPONSE_LEN: usize = RESPONSE_BITS.div_ceil(8) as usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationProof {
+    /// (alpha^k, beta^k) in the group moved from, one per round
+    pub r: Vec<(BigUint, BigUint)>,
+    /// (alpha'^k, beta'^k) in the group moved to, one per round
+    pub t: Vec<(BigUint, BigUint)>,
+    /// k + c * x over the integers, one per round
+    pub s: Vec<BigUint>,
+}
+
+/// ROUNDS challenge bits from H(both groups, both pairs, every commitment,
+/// context)
+#[allow(clippy::too_many_arguments)]
+fn challenge(
+    from: &ChaumPedersen,
+    to: &ChaumPedersen,
+    y: (&BigUint, &BigUint),
+    y_new: (&BigUint, &BigUint),
+    r: &[(BigUint, BigUint)],
+    t: &[(BigUint, BigUint)],
+    context: &[u8],
+) -> Vec<bool> {
+    let mut transcript = Transcript::new(HashFunction::Sha256, b"nmc-migrate");
+    transcript.append(b"from", &from.fingerprint());
+    transcript.append(b"to", &to.fingerprint());
+    transcript.append_biguint(b"y1", y.0);
+    transcript.append_biguint(b"y2", y.1);
+    transcript.append_biguint(b"y1'", y_new.0);
+    transcript.append_biguint(b"y2'", y_new.1);
+    for ((r1, r2), (t1, t2)) in r.iter().zip(t) {
+        transcript.append_biguint(b"r1", r1);
+        transcript.append_biguint(b"r2", r2);
+        transcript.append_biguint(b"t1", t1);
+        transcript.append_biguint(b"t2", t2);
+    }
+    transcript.append(b"context", context);
+
+    let bytes = transcript.challenge_bytes(b"c", ROUNDS / 8);
+    (0..ROUNDS)
+        .map(|i| bytes[i / 8] >> (i % 8) & 1 == 1)
+        .collect()
+}
+
+/// base^s == r * y^c mod p, for the integer response s and a one-bit c;
+/// base has order q, so s is reduced mod q first
+fn check(
+    cp: &ChaumPedersen,
+    base: &BigUint,
+    y: &BigUint,
+    r: &BigUint,
+    c: bool,
+    s: &BigUint,
+) -> bool {
+    let lhs = base.modpow(&(s % &cp.q), &cp.p);
+    if c {
+        lhs == (r * y) % &cp.p
+    } else {
+        lhs == *r
+    }
+}
+
+/// Proof that `x` is the secret behind `from.compute_pair(x)` and
+/// `to.compute_pair(x)`; `context` binds it to one session
+pub fn prove(
+    from: &ChaumPedersen,
+    to: &ChaumPedersen,
+    x: &BigUint,
+    context: &[u8],
+) -> Result<MigrationProof> {
+    if x.bits() > SECRET_BITS {
+        return Err(Error::InvalidParameters(format!(
+            "a {}-bit secret cannot be moved, at most {} bits",
+            x.bits(),
+            SECRET_BITS
+        )));
+    }
+
+    let y = from.compute_pair(x);
+    let y_new = to.compute_pair(x);
+
+    let bound = BigUint::from(1u32) << (SECRET_BITS + MASK_BITS);
+    let k: Vec<BigUint> = (0..ROUNDS)
+        .map(|_| ChaumPedersen::generate_random_below(&bound))
+        .collect();
+    let r: Vec<_> = k.iter().map(|k| from.compute_pair(k)).collect();
+    let t: Vec<_> = k.iter().map(|k| to.compute_pair(k)).collect();
+
+    let c = challenge(
+        from,
+        to,
+        (&y.0, &y.1),
+        (&y_new.0, &y_new.1),
+        &r,
+        &t,
+        context,
+    );
+    let s = k
+        .into_iter()
+        .zip(c)
+        .map(|(k, c)| if c { k + x } else { k })
+        .collect();
+
+    Ok(MigrationProof { r, t, s })
+}
+
+/// Checks that (y1, y2) in `from` and (y1', y2') in `to` share a secret
+#[allow(clippy::too_many_arguments)]
+pub fn verify(
+    from: &ChaumPedersen,
+    to: &ChaumPedersen,
+    y1: &BigUint,
+    y2: &BigUint,
+    y1_new: &BigUint,
+    y2_new: &BigUint,
+    context: &[u8],
+    proof: &MigrationProof,
+) -> bool {
+    if proof.r.len() != ROUNDS || proof.t.len() != ROUNDS || proof.s.len() != ROUNDS {
+        return false;
+    }
+    if proof.s.iter().any(|s| s.bits() > RESPONSE_BITS) {
+        return false;
+    }
+
+    let c = challenge(
+        from,
+        to,
+        (y1, y2),
+        (y1_new, y2_new),
+        &proof.r,
+        &proof.t,
+        context,
+    );
+
+    c.into_iter()
+        .zip(&proof.r)
+        .zip(&proof.t)
+        .zip(&proof.s)
+        .all(|(((c, (r1, r2)), (t1, t2)), s)| {
+            check(from, &from.alpha, y1, r1, c, s)
+                && check(from, &from.beta, y2, r2, c, s)
+                && check(to, &to.alpha, y1_new, t1, c, s)
+                && check(to, &to.beta, y2_new, t2, c, s)
+        })
+}
+
+/// A response as exactly `RESPONSE_LEN` big-endian bytes
+pub fn encode_response(s: &BigUint) -> Vec<u8> {
+    to_fixed_bytes(s, RESPONSE_LEN)
+}
+
+/// The inverse of `encode_response`, rejecting any other length
+pub fn decode_response(bytes: &[u8]) -> Result<BigUint> {
+    if bytes.len() != RESPONSE_LEN {
+        return Err(Error::Decode(format!(
+            "response is {} bytes, expected {}",
+            bytes.len(),
+            RESPONSE_LEN
+        )));
+    }
+    Ok(BigUint::from_bytes_be(bytes))
+}
+
+/// A commitment as `cp.decode_element` reads it, less the subgroup check:
+/// `verify` holds without it, and it would cost an exponentiation by q for
+/// every commitment
+pub fn decode_commitment(cp: &ChaumPedersen, bytes: &[u8]) -> Result<BigUint> {
+    if bytes.len() != cp.element_len() {
+        return Err(Error::Decode(format!(
+            "element is {} bytes, expected {}",
+            bytes.len(),
+            cp.element_len()
+        )));
+    }
+    let value = BigUint::from_bytes_be(bytes);
+    if value >= cp.p {
+        return Err(Error::Decode(format!("{} is not below p", value)));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::groups::GroupId;
+
+    #[test]
+    fn test_prove_verify() {
+        let from = GroupId::Rfc5114.parameters();
+        let to = GroupId::Ffdhe2048.parameters();
+
+        // a PBKDF2 x, wider than the old q and narrower than the new one
+        let kdf = Kdf::Pbkdf2Sha256 {
+            salt: vec![7; Kdf::SALT_LEN],
+            iterations: Kdf::MIN_ITERATIONS,
+        };
+        let x = kdf.derive(b"password");
+        let (y1, y2) = from.compute_pair(&x);
+        let (y1_new, y2_new) = to.compute_pair(&x);
+
+        let proof = prove(&from, &to, &x, b"session-1").unwrap();
+        let verify_with = |y_new: &(BigUint, BigUint), context: &[u8], proof: &MigrationProof| {
+            verify(&from, &to, &y1, &y2, &y_new.0, &y_new.1, context, proof)
+        };
+        let y_new = (y1_new, y2_new);
+        assert!(verify_with(&y_new, b"session-1", &proof));
+
+        // bound to the session
+        assert!(!verify_with(&y_new, b"session-2", &proof));
+
+        // a new pair for some other secret
+        let other = to.compute_pair(&kdf.derive(b"passw0rd"));
+        assert!(!verify_with(&other, b"session-1", &proof));
+
+        // x + q is the same secret to the old group, not to the new one
+        let shifted = to.compute_pair(&(&x + &from.q));
+        assert!(!verify_with(&shifted, b"session-1", &proof));
+
+        let mut tampered = proof.clone();
+        tampered.s[0] += 1u32;
+        assert!(!verify_with(&y_new, b"session-1", &tampered));
+
+        let mut truncated = proof.clone();
+        truncated.s.pop();
+        assert!(!verify_with(&y_new, b"session-1", &truncated));
+
+        // every response fits the wire width
+        for s in &proof.s {
+            assert_eq!(decode_response(&encode_response(s)).unwrap(), *s);
+        }
+        assert!(matches!(
+            decode_response(&[0; RESPONSE_LEN + 1]),
+            Err(Error::Decode(_))
+        ));
+        assert!(matches!(
+            decode_commitment(&to, &to.encode_element(&to.p)),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    /// Different secrets in the two groups, answered mod each q and joined
+    /// by the CRT into one integer response per round
+    #[test]
+    fn test_crt_forgery() {
+        let from = GroupId::Rfc5114.parameters();
+        let to = GroupId::Ffdhe2048.parameters();
+
+        let x_old = ChaumPedersen::generate_random_below(&from.q);
+        let x_new = ChaumPedersen::generate_random_below(&(BigUint::from(1u32) << SECRET_BITS));
+        let (y1, y2) = from.compute_pair(&x_old);
+        let (y1_new, y2_new) = to.compute_pair(&x_new);
+
+        // one commitment repeated in every round is as good as any other
+        let k_old = ChaumPedersen::generate_random_below(&from.q);
+        let k_new = ChaumPedersen::generate_random_below(&(BigUint::from(1u32) << SECRET_BITS));
+        let r = vec![from.compute_pair(&k_old); ROUNDS];
+        let t = vec![to.compute_pair(&k_new); ROUNDS];
+        let c = challenge(
+            &from,
+            &to,
+            (&y1, &y2),
+            (&y1_new, &y2_new),
+            &r,
+            &t,
+            b"session-1",
+        );
+
+        // s = a1 mod q1 and s = a2 mod q2, so every group equation holds
+        let q1q2 = &from.q * &to.q;
+        let inverse = |a: &BigUint, m: &BigUint| a.modpow(&(m - 2u32), m);
+        let (e1, e2) = (
+            &to.q * inverse(&to.q, &from.q),
+            &from.q * inverse(&from.q, &to.q),
+        );
+        let s: Vec<BigUint> = (0..ROUNDS)
+            .map(|i| {
+                let (x1, x2) = if c[i] {
+                    (&x_old, &x_new)
+                } else {
+                    (&BigUint::from(0u32), &BigUint::from(0u32))
+                };
+                let a1 = (&k_old + x1) % &from.q;
+                let a2 = (&k_new + x2) % &to.q;
+                (a1 * &e1 + a2 * &e2) % &q1q2
+            })
+            .collect();
+
+        for (i, s) in s.iter().enumerate().take(4) {
+            assert!(check(&from, &from.alpha, &y1, &r[i].0, c[i], s));
+            assert!(check(&to, &to.alpha, &y1_new, &t[i].0, c[i], s));
+        }
+
+        // but the responses are as wide as q1 * q2, far past RESPONSE_BITS
+        let forgery = MigrationProof { r, t, s };
+        assert!(!verify(
+            &from,
+            &to,
+            &y1,
+            &y2,
+            &y1_new,
+            &y2_new,
+            b"session-1",
+            &forgery
+        ));
+    }
+
+    #[test]
+    fn test_secret_too_large() {
+        let from = GroupId::Rfc5114.parameters();
+        let to = GroupId::Ffdhe2048.parameters();
+        let x = BigUint::from(1u32) << SECRET_BITS;
+
+        assert!(matches!(
+            prove(&from, &to, &x, b"session-1"),
+            Err(Error::InvalidParameters(_))
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{ChaumPedersen, Error, Result};
+use num_bigint::BigUint;
+
+/// f(z) = a_0 + a_1 * z + ... + a_t * z^t mod q
+#[derive(Debug, Clone)]
+pub struct Polynomial {
+    pub coefficients: Vec<BigUint>,
+    pub q: BigUint,
+}
+
+impl Polynomial {
+    /// Random polynomial of the given degree with f(0) = secret
+    pub fn random(secret: &BigUint, degree: usize, q: &BigUint) -> Self {
+        let mut coefficients = vec![secret % q];
+        for _ in 0..degree {
+            coefficients.push(ChaumPedersen::generate_random_below(q));
+        }
+
+        Polynomial {
+            coefficients,
+            q: q.clone(),
+        }
+    }
+
+    /// Horner evaluation of f(z) mod q
+    pub fn evaluate(&self, z: &BigUint) -> BigUint {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(BigUint::from(0u32), |acc, a| (acc * z + a) % &self.q)
+    }
+}
+
+/// 1 <= threshold <= n, so that a polynomial of degree threshold - 1 exists
+/// and n shares can reach it
+pub fn check_threshold(threshold: usize, n: u32) -> Result<()> {
+    if threshold < 1 || threshold > n as usize {
+        return Err(Error::InvalidParameters(format!(
+            "threshold {} out of range for {} holders",
+            threshold, n
+        )));
+    }
+    Ok(())
+}
+
+/// lambda_i = prod_{j != i} j / (j - i) mod q, so that f(0) = sum lambda_i * f(i)
+pub fn lagrange_coefficient(ids: &[u32], i: u32, q: &BigUint) -> BigUint {
+    let mut numerator = BigUint::from(1u32);
+    let mut denominator = BigUint::from(1u32);
+
+    for &j in ids.iter().filter(|&&j| j != i) {
+        numerator = numerator * BigUint::from(j) % q;
+        // j - i mod q
+        let diff = (BigUint::from(j) + q - BigUint::from(i) % q) % q;
+        denominator = denominator * diff % q;
+    }
+
+    // q is prime: d^-1 = d^(q-2) mod q
+    let inverse = denominator.modpow(&(q - BigUint::from(2u32)), q);
+    numerator * inverse % q
+}
+
+/// f(0) from t points (i, f(i)) with distinct non-zero i; a repeated i
+/// would zero a denominator, so it is rejected
+pub fn interpolate(shares: &[(u32, BigUint)], q: &BigUint) -> Result<BigUint> {
+    let ids: Vec<u32> = shares.iter().map(|(i, _)| *i).collect();
+
+    if let Some(i) = ids
+        .iter()
+        .enumerate()
+        .find_map(|(n, i)| ids[..n].contains(i).then_some(i))
+    {
+        return Err(Error::InvalidParameters(format!("share id {} repeated", i)));
+    }
+
+    Ok(shares.iter().fold(BigUint::from(0u32), |acc, (i, value)| {
+        (acc + lagrange_coefficient(&ids, *i, q) * value) % q
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interpolate_any_threshold_subset() {
+        let q = BigUint::from(11u32);
+        let secret = BigUint::from(7u32);
+        let f = Polynomial::random(&secret, 2, &q);
+
+        let shares: Vec<(u32, BigUint)> = (1..=5u32)
+            .map(|i| (i, f.evaluate(&BigUint::from(i))))
+            .collect();
+
+        assert_eq!(interpolate(&shares[..3], &q), Ok(secret.clone()));
+        assert_eq!(interpolate(&shares[2..], &q), Ok(secret.clone()));
+        assert_eq!(
+            interpolate(
+                &[shares[0].clone(), shares[4].clone(), shares[2].clone()],
+                &q
+            ),
+            Ok(secret)
+        );
+        assert!(interpolate(&[shares[0].clone(), shares[0].clone()], &q).is_err());
+    }
+}
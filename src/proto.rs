@@ -0,0 +1,49 @@
+//! The generated gRPC types, shared by `nmc-server` and `nmc-client`, and
+//! their conversions to and from library types.
+
+use nmc_solution::{kdf::Kdf, Error};
+
+pub mod nillion {
+    include!("./nillion.rs");
+}
+
+use nillion::{KdfAlgorithm, KdfParameters};
+
+impl From<&Kdf> for KdfParameters {
+    fn from(kdf: &Kdf) -> Self {
+        match kdf {
+            Kdf::Raw => KdfParameters {
+                algorithm: KdfAlgorithm::KdfRaw.into(),
+                ..Default::default()
+            },
+            Kdf::Pbkdf2Sha256 { salt, iterations } => KdfParameters {
+                algorithm: KdfAlgorithm::KdfPbkdf2Sha256.into(),
+                salt: salt.clone(),
+                iterations: *iterations,
+            },
+        }
+    }
+}
+
+/// A missing kdf is `Kdf::Raw`; unknown algorithms and weak settings are
+/// rejected
+pub fn kdf_from_proto(kdf: Option<KdfParameters>) -> Result<Kdf, Error> {
+    let kdf = match kdf {
+        None => Kdf::Raw,
+        Some(kdf) => match KdfAlgorithm::from_i32(kdf.algorithm) {
+            Some(KdfAlgorithm::KdfRaw) => Kdf::Raw,
+            Some(KdfAlgorithm::KdfPbkdf2Sha256) => Kdf::Pbkdf2Sha256 {
+                salt: kdf.salt,
+                iterations: kdf.iterations,
+            },
+            None => {
+                return Err(Error::InvalidParameters(format!(
+                    "unknown KDF algorithm {}",
+                    kdf.algorithm
+                )))
+            }
+        },
+    };
+    kdf.validate()?;
+    Ok(kdf)
+}
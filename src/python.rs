@@ -0,0 +1,535 @@
+//! PyO3 extension module, enabled by the `python` feature and built with
+//! maturin (see `pyproject.toml`).
+//!
+//! Integers cross the boundary as Python ints; every argument also accepts
+//! big-endian `bytes`, the encoding used on the wire.
+//!
+//! ```python
+//! from nmc_solution import Kdf, Parameters, derive_secret
+//!
+//! params = Parameters.rfc5114()
+//! kdf = Kdf.generate()
+//! x = derive_secret("password", kdf)
+//! y1, y2 = params.compute_pair(x)
+//! assert params.verify_proof(y1, y2, params.prove(x))
+//! ```
+//!
+//! Schnorr signatures, designated-verifier proofs and the Fiat-Shamir
+//! `And`, `Or` and `AnyOf` combinators over Chaum-Pedersen statements are
+//! methods of `Parameters` too; combinator proofs are tuples of ints shaped
+//! like the Rust (commitment, response).
+
+use crate::{
+    designated::{self, DesignatedProof},
+    groups::{self, GroupId},
+    kdf::Kdf,
+    schnorr,
+    schnorr::Signature,
+    sigma::{And, AnyOf, FiatShamir, Or, OrWitness},
+    ChaumPedersen, Error, Proof,
+};
+use num_bigint::BigUint;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+
+/// A non-negative int, or big-endian bytes
+struct Integer(BigUint);
+
+impl<'py> FromPyObject<'py> for Integer {
+    fn extract_bound(value: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(bytes) = value.downcast::<PyBytes>() {
+            return Ok(Integer(BigUint::from_bytes_be(bytes.as_bytes())));
+        }
+        Ok(Integer(value.extract()?))
+    }
+}
+
+/// (y1, y2), (r1, r2) or (c, s) as passed in from Python
+type Pair = (Integer, Integer);
+
+fn pair((first, second): Pair) -> (BigUint, BigUint) {
+    (first.0, second.0)
+}
+
+fn pairs(values: Vec<Pair>) -> Vec<(BigUint, BigUint)> {
+    values.into_iter().map(pair).collect()
+}
+
+/// Every library error surfaces as ValueError
+impl From<Error> for PyErr {
+    fn from(error: Error) -> Self {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+/// How x is derived from the password, as the server records it
+#[pyclass(name = "Kdf", module = "nmc_solution", eq, frozen)]
+#[derive(PartialEq)]
+struct PyKdf(Kdf);
+
+#[pymethods]
+impl PyKdf {
+    /// The password's bytes as x, for users registered before PBKDF2
+    #[staticmethod]
+    fn raw() -> Self {
+        PyKdf(Kdf::Raw)
+    }
+
+    /// PBKDF2-HMAC-SHA256 with the given settings, refused if the server
+    /// would refuse them
+    #[staticmethod]
+    #[pyo3(signature = (salt, iterations = Kdf::DEFAULT_ITERATIONS))]
+    fn pbkdf2_sha256(salt: &[u8], iterations: u32) -> Result<Self, Error> {
+        let kdf = Kdf::Pbkdf2Sha256 {
+            salt: salt.to_vec(),
+            iterations,
+        };
+        kdf.validate()?;
+        Ok(PyKdf(kdf))
+    }
+
+    /// PBKDF2 with a fresh random salt, for a new registration
+    #[staticmethod]
+    fn generate() -> Self {
+        PyKdf(Kdf::pbkdf2_sha256())
+    }
+
+    /// The proto's `KdfAlgorithm`: 0 for raw, 1 for PBKDF2-HMAC-SHA256
+    #[getter]
+    fn algorithm(&self) -> u32 {
+        match self.0 {
+            Kdf::Raw => 0,
+            Kdf::Pbkdf2Sha256 { .. } => 1,
+        }
+    }
+
+    /// Empty for raw
+    #[getter]
+    fn salt<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        match &self.0 {
+            Kdf::Raw => PyBytes::new_bound(py, &[]),
+            Kdf::Pbkdf2Sha256 { salt, .. } => PyBytes::new_bound(py, salt),
+        }
+    }
+
+    /// 0 for raw
+    #[getter]
+    fn iterations(&self) -> u32 {
+        match self.0 {
+            Kdf::Raw => 0,
+            Kdf::Pbkdf2Sha256 { iterations, .. } => iterations,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.0 {
+            Kdf::Raw => "Kdf.raw()".into(),
+            Kdf::Pbkdf2Sha256 { salt, iterations } => format!(
+                "Kdf.pbkdf2_sha256(bytes.fromhex('{}'), {})",
+                hex::encode(salt),
+                iterations
+            ),
+        }
+    }
+}
+
+/// x from the password, as `nmc-client` derives it with the same `kdf`
+#[pyfunction]
+fn derive_secret(password: &str, kdf: &PyKdf) -> BigUint {
+    kdf.0.derive(password.as_bytes())
+}
+
+/// Non-interactive proof (r1, r2, s)
+#[pyclass(name = "Proof", module = "nmc_solution", eq, frozen)]
+#[derive(PartialEq)]
+struct PyProof(Proof);
+
+#[pymethods]
+impl PyProof {
+    #[new]
+    fn new(r1: Integer, r2: Integer, s: Integer) -> Self {
+        PyProof(Proof {
+            r1: r1.0,
+            r2: r2.0,
+            s: s.0,
+        })
+    }
+
+    #[getter]
+    fn r1(&self) -> BigUint {
+        self.0.r1.clone()
+    }
+
+    #[getter]
+    fn r2(&self) -> BigUint {
+        self.0.r2.clone()
+    }
+
+    #[getter]
+    fn s(&self) -> BigUint {
+        self.0.s.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Proof(r1={}, r2={}, s={})", self.0.r1, self.0.r2, self.0.s)
+    }
+}
+
+/// Schnorr signature (r, s) under y = alpha^x
+#[pyclass(name = "Signature", module = "nmc_solution", eq, frozen)]
+#[derive(PartialEq)]
+struct PySignature(Signature);
+
+#[pymethods]
+impl PySignature {
+    #[new]
+    fn new(r: Integer, s: Integer) -> Self {
+        PySignature(Signature { r: r.0, s: s.0 })
+    }
+
+    #[getter]
+    fn r(&self) -> BigUint {
+        self.0.r.clone()
+    }
+
+    #[getter]
+    fn s(&self) -> BigUint {
+        self.0.s.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Signature(r={}, s={})", self.0.r, self.0.s)
+    }
+}
+
+/// Designated-verifier proof (r1, r2, c1, s1, t, c2, s2)
+#[pyclass(name = "DesignatedProof", module = "nmc_solution", eq, frozen)]
+#[derive(PartialEq)]
+struct PyDesignatedProof(DesignatedProof);
+
+#[pymethods]
+impl PyDesignatedProof {
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        r1: Integer,
+        r2: Integer,
+        c1: Integer,
+        s1: Integer,
+        t: Integer,
+        c2: Integer,
+        s2: Integer,
+    ) -> Self {
+        PyDesignatedProof(DesignatedProof {
+            r1: r1.0,
+            r2: r2.0,
+            c1: c1.0,
+            s1: s1.0,
+            t: t.0,
+            c2: c2.0,
+            s2: s2.0,
+        })
+    }
+
+    #[getter]
+    fn r1(&self) -> BigUint {
+        self.0.r1.clone()
+    }
+
+    #[getter]
+    fn r2(&self) -> BigUint {
+        self.0.r2.clone()
+    }
+
+    #[getter]
+    fn c1(&self) -> BigUint {
+        self.0.c1.clone()
+    }
+
+    #[getter]
+    fn s1(&self) -> BigUint {
+        self.0.s1.clone()
+    }
+
+    #[getter]
+    fn t(&self) -> BigUint {
+        self.0.t.clone()
+    }
+
+    #[getter]
+    fn c2(&self) -> BigUint {
+        self.0.c2.clone()
+    }
+
+    #[getter]
+    fn s2(&self) -> BigUint {
+        self.0.s2.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        let proof = &self.0;
+        format!(
+            "DesignatedProof(r1={}, r2={}, c1={}, s1={}, t={}, c2={}, s2={})",
+            proof.r1, proof.r2, proof.c1, proof.s1, proof.t, proof.c2, proof.s2
+        )
+    }
+}
+
+/// ((r1, r2), (r1', r2')) and (s, s')
+type AndProof = (((BigUint, BigUint), (BigUint, BigUint)), (BigUint, BigUint));
+/// ((r1, r2), (r1', r2')) and (c, s, s')
+type OrProof = (
+    ((BigUint, BigUint), (BigUint, BigUint)),
+    (BigUint, BigUint, BigUint),
+);
+/// [(r1, r2), ...] and [(c, s), ...]
+type AnyOfProof = (Vec<(BigUint, BigUint)>, Vec<(BigUint, BigUint)>);
+
+/// Group parameters (p, q, alpha, beta)
+#[pyclass(name = "Parameters", module = "nmc_solution", frozen)]
+struct PyParameters(ChaumPedersen);
+
+impl PyParameters {
+    fn from_tuple((alpha, beta, p, q): (BigUint, BigUint, BigUint, BigUint)) -> Self {
+        PyParameters(ChaumPedersen { alpha, beta, p, q })
+    }
+}
+
+#[pymethods]
+impl PyParameters {
+    /// Raises ValueError unless alpha and beta lie in the order-q subgroup
+    #[new]
+    fn new(p: Integer, q: Integer, alpha: Integer, beta: Integer) -> PyResult<Self> {
+        Ok(PyParameters(ChaumPedersen::new(p.0, q.0, alpha.0, beta.0)?))
+    }
+
+    /// The group a proto `group_id` names, as GetUserParameters returns it;
+    /// raises ValueError for an unknown id
+    #[staticmethod]
+    fn standard(group_id: u32) -> Result<Self, Error> {
+        Ok(PyParameters(GroupId::try_from(group_id)?.parameters()))
+    }
+
+    /// The 1024-bit RFC 5114 group, `group_id` 1, which older registrations
+    /// are in
+    #[staticmethod]
+    fn rfc5114() -> Self {
+        Self::from_tuple(ChaumPedersen::get_constants())
+    }
+
+    #[staticmethod]
+    fn ffdhe2048() -> Self {
+        Self::from_tuple(groups::ffdhe2048())
+    }
+
+    #[staticmethod]
+    fn ffdhe3072() -> Self {
+        Self::from_tuple(groups::ffdhe3072())
+    }
+
+    #[staticmethod]
+    fn ffdhe4096() -> Self {
+        Self::from_tuple(groups::ffdhe4096())
+    }
+
+    #[getter]
+    fn p(&self) -> BigUint {
+        self.0.p.clone()
+    }
+
+    #[getter]
+    fn q(&self) -> BigUint {
+        self.0.q.clone()
+    }
+
+    #[getter]
+    fn alpha(&self) -> BigUint {
+        self.0.alpha.clone()
+    }
+
+    #[getter]
+    fn beta(&self) -> BigUint {
+        self.0.beta.clone()
+    }
+
+    /// Uniform in [0, q), for nonces and challenges
+    fn random_scalar(&self) -> BigUint {
+        ChaumPedersen::generate_random_below(&self.0.q)
+    }
+
+    /// (alpha^exp mod p, beta^exp mod p)
+    fn compute_pair(&self, exp: Integer) -> (BigUint, BigUint) {
+        self.0.compute_pair(&exp.0)
+    }
+
+    /// s = k - c * x mod q
+    fn solve(&self, k: Integer, c: Integer, x: Integer) -> BigUint {
+        self.0.solve(&k.0, &c.0, &x.0)
+    }
+
+    /// r1 == alpha^s * y1^c and r2 == beta^s * y2^c
+    fn verify(
+        &self,
+        y1: Integer,
+        y2: Integer,
+        r1: Integer,
+        r2: Integer,
+        c: Integer,
+        s: Integer,
+    ) -> bool {
+        self.0.verify(&y1.0, &y2.0, &r1.0, &r2.0, &c.0, &s.0)
+    }
+
+    fn is_element(&self, value: Integer) -> bool {
+        self.0.is_element(&value.0)
+    }
+
+    /// Non-interactive proof of knowledge of x
+    fn prove(&self, x: Integer) -> PyProof {
+        PyProof(self.0.prove(&x.0))
+    }
+
+    fn verify_proof(&self, y1: Integer, y2: Integer, proof: &PyProof) -> bool {
+        self.0.verify_proof(&y1.0, &y2.0, &proof.0)
+    }
+
+    /// Schnorr signature on `message` under y = alpha^x
+    fn sign(&self, x: Integer, message: &[u8]) -> PySignature {
+        PySignature(schnorr::sign(&self.0, &x.0, message))
+    }
+
+    fn verify_signature(&self, y: Integer, message: &[u8], signature: &PySignature) -> bool {
+        schnorr::verify(&self.0, &y.0, message, &signature.0)
+    }
+
+    /// Proof of x for (y1, y2) that convinces only the holder of log_alpha(yv)
+    fn prove_designated(&self, x: Integer, yv: Integer, context: &[u8]) -> PyDesignatedProof {
+        PyDesignatedProof(designated::prove(&self.0, &x.0, &yv.0, context))
+    }
+
+    fn verify_designated(
+        &self,
+        y1: Integer,
+        y2: Integer,
+        yv: Integer,
+        context: &[u8],
+        proof: &PyDesignatedProof,
+    ) -> bool {
+        designated::verify(&self.0, &y1.0, &y2.0, &yv.0, context, &proof.0)
+    }
+
+    /// The proof the designated verifier can make with w alone
+    fn forge_designated(
+        &self,
+        w: Integer,
+        y1: Integer,
+        y2: Integer,
+        context: &[u8],
+    ) -> PyDesignatedProof {
+        PyDesignatedProof(designated::forge(&self.0, &w.0, &y1.0, &y2.0, context))
+    }
+
+    /// Fiat-Shamir proof of both secrets behind two (y1, y2) pairs
+    fn prove_and(
+        &self,
+        statements: (Pair, Pair),
+        witnesses: Pair,
+        context: &[u8],
+    ) -> Result<AndProof, Error> {
+        FiatShamir(And(self.0.clone(), self.0.clone())).prove(
+            &(pair(statements.0), pair(statements.1)),
+            &pair(witnesses),
+            context,
+        )
+    }
+
+    fn verify_and(
+        &self,
+        statements: (Pair, Pair),
+        context: &[u8],
+        proof: ((Pair, Pair), Pair),
+    ) -> bool {
+        let ((first, second), response) = proof;
+        FiatShamir(And(self.0.clone(), self.0.clone())).verify(
+            &(pair(statements.0), pair(statements.1)),
+            context,
+            &((pair(first), pair(second)), pair(response)),
+        )
+    }
+
+    /// Fiat-Shamir proof of the secret behind one of two (y1, y2) pairs,
+    /// `index` 0 or 1, without saying which
+    fn prove_or(
+        &self,
+        statements: (Pair, Pair),
+        index: usize,
+        x: Integer,
+        context: &[u8],
+    ) -> Result<OrProof, Error> {
+        let witness = match index {
+            0 => OrWitness::Left(x.0),
+            1 => OrWitness::Right(x.0),
+            _ => {
+                return Err(Error::InvalidParameters(format!(
+                    "Or: index {} is neither 0 nor 1",
+                    index
+                )))
+            }
+        };
+        FiatShamir(Or(self.0.clone(), self.0.clone())).prove(
+            &(pair(statements.0), pair(statements.1)),
+            &witness,
+            context,
+        )
+    }
+
+    fn verify_or(
+        &self,
+        statements: (Pair, Pair),
+        context: &[u8],
+        proof: ((Pair, Pair), (Integer, Integer, Integer)),
+    ) -> bool {
+        let ((first, second), (c, s_a, s_b)) = proof;
+        FiatShamir(Or(self.0.clone(), self.0.clone())).verify(
+            &(pair(statements.0), pair(statements.1)),
+            context,
+            &((pair(first), pair(second)), (c.0, s_a.0, s_b.0)),
+        )
+    }
+
+    /// Fiat-Shamir proof of the secret behind `statements[index]` that
+    /// hides the index among all of them
+    fn prove_any_of(
+        &self,
+        statements: Vec<Pair>,
+        index: usize,
+        x: Integer,
+        context: &[u8],
+    ) -> Result<AnyOfProof, Error> {
+        FiatShamir(AnyOf(self.0.clone())).prove(&pairs(statements), &(index, x.0), context)
+    }
+
+    fn verify_any_of(
+        &self,
+        statements: Vec<Pair>,
+        context: &[u8],
+        proof: (Vec<Pair>, Vec<Pair>),
+    ) -> bool {
+        let (commitments, responses) = proof;
+        FiatShamir(AnyOf(self.0.clone())).verify(
+            &pairs(statements),
+            context,
+            &(pairs(commitments), pairs(responses)),
+        )
+    }
+}
+
+#[pymodule]
+fn nmc_solution(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyParameters>()?;
+    module.add_class::<PyProof>()?;
+    module.add_class::<PySignature>()?;
+    module.add_class::<PyDesignatedProof>()?;
+    module.add_class::<PyKdf>()?;
+    module.add_function(wrap_pyfunction!(derive_secret, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,265 @@
+//! Abe-Ohkubo-Suzuki ring signatures over registered `y1` keys.
+//!
+//! The signer closes a ring of Schnorr challenges c_{i+1} = H(..., alpha^s_i *
+//! y_i^c_i): every other member's response is random, and only the real
+//! signer, who knows x for their y, can answer the challenge that lands on
+//! them. A verifier walks the ring from c_0 and checks it closes, learning
+//! that some member signed but not which.
+//!
+//! The linkable variant also publishes a key image I = h^x, with h hashed
+//! from a scope, and runs the same ring over (alpha, y_i) and (h, I) as a
+//! Chaum-Pedersen 1-of-n proof. Two signatures from one key in one scope
+//! carry the same I, which exposes double use without revealing the signer.
+
+use crate::{ChaumPedersen, Error, Result};
+use num_bigint::BigUint;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RingSignature {
+    pub c0: BigUint,
+    pub s: Vec<BigUint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkableSignature {
+    pub c0: BigUint,
+    pub s: Vec<BigUint>,
+    /// I = h^x, the same for every signature by x in one scope
+    pub key_image: BigUint,
+}
+
+/// Second generator h and key image I of the linkable variant
+type Link<'a> = Option<(&'a BigUint, &'a BigUint)>;
+
+/// c = H(p, q, alpha, ring, message, h, I, a, b)
+fn challenge(
+    cp: &ChaumPedersen,
+    ring: &[BigUint],
+    message: &[u8],
+    link: Link,
+    (a, b): (&BigUint, Option<&BigUint>),
+) -> BigUint {
+    let mut items = vec![
+        cp.p.to_bytes_be(),
+        cp.q.to_bytes_be(),
+        cp.alpha.to_bytes_be(),
+        (ring.len() as u64).to_be_bytes().to_vec(),
+    ];
+    items.extend(ring.iter().map(BigUint::to_bytes_be));
+    items.push(message.to_vec());
+    if let Some((h, key_image)) = link {
+        items.push(h.to_bytes_be());
+        items.push(key_image.to_bytes_be());
+    }
+    items.push(a.to_bytes_be());
+    if let Some(b) = b {
+        items.push(b.to_bytes_be());
+    }
+
+    let items: Vec<&[u8]> = items.iter().map(Vec::as_slice).collect();
+    cp.hash_to_challenge(&items)
+}
+
+/// (alpha^s * y^c, h^s * I^c)
+fn round(
+    cp: &ChaumPedersen,
+    y: &BigUint,
+    link: Link,
+    c: &BigUint,
+    s: &BigUint,
+) -> (BigUint, Option<BigUint>) {
+    let a = (cp.alpha.modpow(s, &cp.p) * y.modpow(c, &cp.p)) % &cp.p;
+    let b = link.map(|(h, key_image)| (h.modpow(s, &cp.p) * key_image.modpow(c, &cp.p)) % &cp.p);
+    (a, b)
+}
+
+fn sign_ring(
+    cp: &ChaumPedersen,
+    ring: &[BigUint],
+    x: &BigUint,
+    message: &[u8],
+    link: Link,
+) -> Result<(BigUint, Vec<BigUint>)> {
+    let y = cp.alpha.modpow(x, &cp.p);
+    let signer = ring
+        .iter()
+        .position(|member| *member == y)
+        .ok_or_else(|| Error::NotFound("alpha^x is not a member of the ring".into()))?;
+    let n = ring.len();
+
+    let mut c = vec![BigUint::default(); n];
+    let mut s = vec![BigUint::default(); n];
+
+    let k = ChaumPedersen::generate_random_below(&cp.q);
+    let a = cp.alpha.modpow(&k, &cp.p);
+    let b = link.map(|(h, _)| h.modpow(&k, &cp.p));
+    c[(signer + 1) % n] = challenge(cp, ring, message, link, (&a, b.as_ref()));
+
+    // everyone else's response is simulated
+    for offset in 1..n {
+        let i = (signer + offset) % n;
+        s[i] = ChaumPedersen::generate_random_below(&cp.q);
+        let (a, b) = round(cp, &ring[i], link, &c[i], &s[i]);
+        c[(i + 1) % n] = challenge(cp, ring, message, link, (&a, b.as_ref()));
+    }
+
+    s[signer] = cp.solve(&k, &c[signer], x);
+
+    Ok((c.swap_remove(0), s))
+}
+
+fn verify_ring(
+    cp: &ChaumPedersen,
+    ring: &[BigUint],
+    message: &[u8],
+    link: Link,
+    c0: &BigUint,
+    s: &[BigUint],
+) -> bool {
+    if ring.is_empty()
+        || s.len() != ring.len()
+        || *c0 >= cp.q
+        || s.iter().any(|s| *s >= cp.q)
+        || !ring.iter().all(|y| cp.is_element(y))
+    {
+        return false;
+    }
+
+    let mut c = c0.clone();
+    for (y, s) in ring.iter().zip(s) {
+        let (a, b) = round(cp, y, link, &c, s);
+        c = challenge(cp, ring, message, link, (&a, b.as_ref()));
+    }
+
+    c == *c0
+}
+
+/// Signs for one of `ring`; `Error::NotFound` if alpha^x is not a member
+pub fn sign(
+    cp: &ChaumPedersen,
+    ring: &[BigUint],
+    x: &BigUint,
+    message: &[u8],
+) -> Result<RingSignature> {
+    let (c0, s) = sign_ring(cp, ring, x, message, None)?;
+    Ok(RingSignature { c0, s })
+}
+
+pub fn verify(
+    cp: &ChaumPedersen,
+    ring: &[BigUint],
+    message: &[u8],
+    signature: &RingSignature,
+) -> bool {
+    verify_ring(cp, ring, message, None, &signature.c0, &signature.s)
+}
+
+/// h = H(scope) in the order-q subgroup, with unknown log_alpha(h)
+pub fn link_base(cp: &ChaumPedersen, scope: &[u8]) -> BigUint {
+    cp.hash_to_group(&[b"ring-link", scope])
+}
+
+pub fn sign_linkable(
+    cp: &ChaumPedersen,
+    ring: &[BigUint],
+    x: &BigUint,
+    message: &[u8],
+    scope: &[u8],
+) -> Result<LinkableSignature> {
+    let h = link_base(cp, scope);
+    let key_image = h.modpow(x, &cp.p);
+
+    let (c0, s) = sign_ring(cp, ring, x, message, Some((&h, &key_image)))?;
+    Ok(LinkableSignature { c0, s, key_image })
+}
+
+pub fn verify_linkable(
+    cp: &ChaumPedersen,
+    ring: &[BigUint],
+    message: &[u8],
+    scope: &[u8],
+    signature: &LinkableSignature,
+) -> bool {
+    let h = link_base(cp, scope);
+
+    cp.is_element(&signature.key_image)
+        && verify_ring(
+            cp,
+            ring,
+            message,
+            Some((&h, &signature.key_image)),
+            &signature.c0,
+            &signature.s,
+        )
+}
+
+/// Both valid signatures in the same scope came from the same key
+pub fn linked(a: &LinkableSignature, b: &LinkableSignature) -> bool {
+    a.key_image == b.key_image
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    fn keys(cp: &ChaumPedersen, n: usize) -> (Vec<BigUint>, Vec<BigUint>) {
+        let secrets: Vec<BigUint> = (0..n)
+            .map(|_| ChaumPedersen::generate_random_below(&cp.q))
+            .collect();
+        let ring = secrets.iter().map(|x| cp.alpha.modpow(x, &cp.p)).collect();
+        (secrets, ring)
+    }
+
+    #[test]
+    fn test_ring_sign_verify() {
+        let cp = constants();
+        let (secrets, ring) = keys(&cp, 4);
+
+        for x in &secrets {
+            let signature = sign(&cp, &ring, x, b"hello").unwrap();
+            assert!(verify(&cp, &ring, b"hello", &signature));
+            assert!(!verify(&cp, &ring, b"bye", &signature));
+            assert!(!verify(&cp, &ring[1..], b"hello", &signature));
+        }
+
+        // a single-member ring is a Schnorr signature
+        let signature = sign(&cp, &ring[..1], &secrets[0], b"hello").unwrap();
+        assert!(verify(&cp, &ring[..1], b"hello", &signature));
+
+        // not a member
+        let outsider = ChaumPedersen::generate_random_below(&cp.q);
+        assert!(matches!(
+            sign(&cp, &ring, &outsider, b"hello"),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_linkable() {
+        let cp = constants();
+        let (secrets, ring) = keys(&cp, 3);
+
+        let first = sign_linkable(&cp, &ring, &secrets[0], b"vote A", b"poll-1").unwrap();
+        let second = sign_linkable(&cp, &ring, &secrets[0], b"vote B", b"poll-1").unwrap();
+        let other = sign_linkable(&cp, &ring, &secrets[1], b"vote A", b"poll-1").unwrap();
+        let elsewhere = sign_linkable(&cp, &ring, &secrets[0], b"vote A", b"poll-2").unwrap();
+
+        assert!(verify_linkable(&cp, &ring, b"vote A", b"poll-1", &first));
+        assert!(verify_linkable(&cp, &ring, b"vote B", b"poll-1", &second));
+        assert!(!verify_linkable(&cp, &ring, b"vote A", b"poll-2", &first));
+
+        assert!(linked(&first, &second));
+        assert!(!linked(&first, &other));
+        assert!(!linked(&first, &elsewhere));
+
+        // a key image from another signer does not fit the proof
+        let mut swapped = first.clone();
+        swapped.key_image = other.key_image.clone();
+        assert!(!verify_linkable(&cp, &ring, b"vote A", b"poll-1", &swapped));
+    }
+}
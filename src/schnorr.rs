@@ -0,0 +1,170 @@
+//! Schnorr signatures over the Chaum-Pedersen group.
+//!
+//! A signature is the single-generator half of the login protocol made
+//! non-interactive: the commitment r = alpha^k, a Fiat-Shamir challenge
+//! e = H(r, y, message) and the response s = k - e * x mod q from `solve`.
+//! It verifies as r == alpha^s * y^e mod p, where y = alpha^x is the `y1` a
+//! user registers with `nmc-server`.
+
+use crate::ChaumPedersen;
+use num_bigint::{BigUint, RandBigInt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub r: BigUint,
+    pub s: BigUint,
+}
+
+/// e = H(p, q, alpha, r, y, message)
+pub fn challenge(cp: &ChaumPedersen, r: &BigUint, y: &BigUint, message: &[u8]) -> BigUint {
+    cp.hash_to_challenge(&[
+        &cp.p.to_bytes_be(),
+        &cp.q.to_bytes_be(),
+        &cp.alpha.to_bytes_be(),
+        &r.to_bytes_be(),
+        &y.to_bytes_be(),
+        message,
+    ])
+}
+
+pub fn sign(cp: &ChaumPedersen, x: &BigUint, message: &[u8]) -> Signature {
+    let y = cp.alpha.modpow(x, &cp.p);
+    let k = ChaumPedersen::generate_random_below(&cp.q);
+    let r = cp.alpha.modpow(&k, &cp.p);
+    let e = challenge(cp, &r, &y, message);
+    let s = cp.solve(&k, &e, x);
+
+    Signature { r, s }
+}
+
+/// Every check `batch_verify` makes on one entry: s < q, r and y in the
+/// order-q subgroup
+fn well_formed(cp: &ChaumPedersen, y: &BigUint, signature: &Signature) -> bool {
+    signature.s < cp.q && cp.is_element(&signature.r) && cp.is_element(y)
+}
+
+/// r == alpha^s * y^e mod p, with r, y and s checked as `batch_verify` does
+pub fn verify(cp: &ChaumPedersen, y: &BigUint, message: &[u8], signature: &Signature) -> bool {
+    if !well_formed(cp, y, signature) {
+        return false;
+    }
+
+    let e = challenge(cp, &signature.r, y, message);
+    let rhs = (cp.alpha.modpow(&signature.s, &cp.p) * y.modpow(&e, &cp.p)) % &cp.p;
+
+    signature.r == rhs
+}
+
+/// Checks prod r_i^z_i == alpha^(sum z_i * s_i) * prod y_i^(z_i * e_i) for
+/// random 128-bit z_i, which accepts a forged entry with probability 2^-128
+pub fn batch_verify(cp: &ChaumPedersen, items: &[(&BigUint, &[u8], &Signature)]) -> bool {
+    let mut rng = rand::thread_rng();
+    let mut lhs = BigUint::from(1u32);
+    let mut rhs = BigUint::from(1u32);
+    let mut exponent = BigUint::from(0u32);
+
+    for (y, message, signature) in items {
+        // the combined equation is only sound inside the order-q subgroup
+        if !well_formed(cp, y, signature) {
+            return false;
+        }
+
+        let z = rng.gen_biguint(128);
+        let e = challenge(cp, &signature.r, y, message);
+
+        lhs = (lhs * signature.r.modpow(&z, &cp.p)) % &cp.p;
+        rhs = (rhs * y.modpow(&((&z * e) % &cp.q), &cp.p)) % &cp.p;
+        exponent = (exponent + z * &signature.s) % &cp.q;
+    }
+
+    lhs == (rhs * cp.alpha.modpow(&exponent, &cp.p)) % &cp.p
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, _) = cp.compute_pair(&x);
+
+        let signature = sign(&cp, &x, b"hello");
+        assert!(verify(&cp, &y1, b"hello", &signature));
+        assert!(!verify(&cp, &y1, b"hellO", &signature));
+
+        let other = ChaumPedersen::generate_random_below(&cp.q);
+        let (y_other, _) = cp.compute_pair(&other);
+        assert!(!verify(&cp, &y_other, b"hello", &signature));
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let cp = constants();
+        let keys: Vec<(BigUint, BigUint)> = (0..4)
+            .map(|_| {
+                let x = ChaumPedersen::generate_random_below(&cp.q);
+                let y = cp.alpha.modpow(&x, &cp.p);
+                (x, y)
+            })
+            .collect();
+        let messages: [&[u8]; 4] = [b"a", b"b", b"c", b"d"];
+
+        let mut signatures: Vec<Signature> = keys
+            .iter()
+            .zip(messages.iter())
+            .map(|((x, _), m)| sign(&cp, x, m))
+            .collect();
+
+        let check = |signatures: &[Signature]| -> bool {
+            let items: Vec<(&BigUint, &[u8], &Signature)> = keys
+                .iter()
+                .zip(messages.iter())
+                .zip(signatures.iter())
+                .map(|(((_, y), m), s)| (y, *m, s))
+                .collect();
+            batch_verify(&cp, &items)
+        };
+
+        assert!(check(&signatures));
+
+        signatures[2].s = (&signatures[2].s + 1u32) % &cp.q;
+        assert!(!check(&signatures));
+    }
+
+    #[test]
+    fn test_outside_subgroup_is_rejected() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+
+        // y' = -alpha^x has order 2q; with an even challenge the single
+        // equation r == alpha^s * y'^e still holds
+        let y = &cp.p - cp.alpha.modpow(&x, &cp.p);
+        let signature = loop {
+            let k = ChaumPedersen::generate_random_below(&cp.q);
+            let r = cp.alpha.modpow(&k, &cp.p);
+            let e = challenge(&cp, &r, &y, b"hello");
+            if !e.bit(0) {
+                break Signature {
+                    s: cp.solve(&k, &e, &x),
+                    r,
+                };
+            }
+        };
+        let e = challenge(&cp, &signature.r, &y, b"hello");
+        assert_eq!(
+            signature.r,
+            (cp.alpha.modpow(&signature.s, &cp.p) * y.modpow(&e, &cp.p)) % &cp.p
+        );
+
+        assert!(!verify(&cp, &y, b"hello", &signature));
+        let message: &[u8] = b"hello";
+        assert!(!batch_verify(&cp, &[(&y, message, &signature)]));
+    }
+}
@@ -0,0 +1,206 @@
+//! Serde support for integers, enabled by the `serde` feature.
+//!
+//! Human-readable formats (JSON, TOML, ...) get a lowercase hex string of
+//! the minimal big-endian bytes, without the zero padding the proto adds to
+//! reach the group's fixed width; binary formats (bincode, CBOR, postcard,
+//! ...) get the bytes themselves. Use it on any `Backend` field with
+//! `#[serde(with = "nmc_solution::serialize")]`.
+//!
+//! `ChaumPedersen` is read into `UncheckedParameters` first and only kept if
+//! it passes the checks of `ChaumPedersen::new`.
+
+use crate::{backend::Backend, ChaumPedersen, Error};
+use alloc::vec::Vec;
+use core::fmt;
+use num_bigint::BigUint;
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserializer, Serializer,
+};
+
+/// (p, q, alpha, beta) as read, before validation
+#[derive(serde::Deserialize)]
+#[serde(bound = "I: Backend")]
+pub(crate) struct UncheckedParameters<I> {
+    #[serde(with = "self")]
+    p: I,
+    #[serde(with = "self")]
+    q: I,
+    #[serde(with = "self")]
+    alpha: I,
+    #[serde(with = "self")]
+    beta: I,
+}
+
+impl<I: Backend> TryFrom<UncheckedParameters<I>> for ChaumPedersen<I> {
+    type Error = Error;
+
+    fn try_from(unchecked: UncheckedParameters<I>) -> Result<Self, Error> {
+        ChaumPedersen {
+            p: unchecked.p,
+            q: unchecked.q,
+            alpha: unchecked.alpha,
+            beta: unchecked.beta,
+        }
+        .validate()
+    }
+}
+
+pub fn serialize<I: Backend, S: Serializer>(value: &I, serializer: S) -> Result<S::Ok, S::Error> {
+    let bytes = value.to_biguint().to_bytes_be();
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&hex::encode(bytes))
+    } else {
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+pub fn deserialize<'de, I: Backend, D: Deserializer<'de>>(deserializer: D) -> Result<I, D::Error> {
+    let value = if deserializer.is_human_readable() {
+        deserializer.deserialize_str(BigEndian)?
+    } else {
+        deserializer.deserialize_bytes(BigEndian)?
+    };
+    I::from_biguint(&value).ok_or_else(|| de::Error::custom("integer does not fit the backend"))
+}
+
+/// Accepts hex strings and byte strings, plus sequences of bytes for formats
+/// that have no byte string type
+struct BigEndian;
+
+impl<'de> Visitor<'de> for BigEndian {
+    type Value = BigUint;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hex string or big-endian bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<BigUint, E> {
+        let bytes = hex::decode(value).map_err(E::custom)?;
+        Ok(BigUint::from_bytes_be(&bytes))
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<BigUint, E> {
+        Ok(BigUint::from_bytes_be(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BigUint, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        Ok(BigUint::from_bytes_be(&bytes))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::{ChaumPedersen, Commitment, InteractiveTranscript, Proof, PublicKey};
+    use num_bigint::BigUint;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    fn transcript(cp: &ChaumPedersen) -> InteractiveTranscript {
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let k = ChaumPedersen::generate_random_below(&cp.q);
+        let c = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, y2) = cp.compute_pair(&x);
+        let (r1, r2) = cp.compute_pair(&k);
+
+        InteractiveTranscript {
+            public_key: PublicKey { y1, y2 },
+            commitment: Commitment { r1, r2 },
+            s: cp.solve(&k, &c, &x),
+            c,
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let cp = constants();
+        let transcript = transcript(&cp);
+        let proof = cp.prove(&ChaumPedersen::generate_random_below(&cp.q));
+
+        let json = serde_json::to_string(&cp).unwrap();
+        assert_eq!(serde_json::from_str::<ChaumPedersen>(&json).unwrap(), cp);
+        assert!(json.contains(&format!("\"q\":\"{}\"", hex::encode(cp.q.to_bytes_be()))));
+
+        let json = serde_json::to_string(&transcript).unwrap();
+        let decoded: InteractiveTranscript = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, transcript);
+        assert!(decoded.verify(&cp));
+
+        let json = serde_json::to_string(&proof).unwrap();
+        assert_eq!(serde_json::from_str::<Proof>(&json).unwrap(), proof);
+
+        let key = PublicKey {
+            y1: BigUint::from(7u32),
+            y2: BigUint::from(0x0100u32),
+        };
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(json, r#"{"y1":"07","y2":"0100"}"#);
+        assert_eq!(serde_json::from_str::<PublicKey>(&json).unwrap(), key);
+        assert!(serde_json::from_str::<PublicKey>(r#"{"y1":"zz","y2":"01"}"#).is_err());
+    }
+
+    #[test]
+    fn test_invalid_parameters_are_rejected() {
+        // 5 is not in the order-11 subgroup of Z_23*
+        let json = r#"{"p":"17","q":"0b","alpha":"05","beta":"09"}"#;
+        let error = serde_json::from_str::<ChaumPedersen>(json).unwrap_err();
+        assert!(error.to_string().contains("order-q subgroup"));
+
+        let json = r#"{"p":"17","q":"0b","alpha":"04","beta":"09"}"#;
+        let cp = serde_json::from_str::<ChaumPedersen>(json).unwrap();
+        assert_eq!(
+            cp,
+            ChaumPedersen::new(23u32.into(), 11u32.into(), 4u32.into(), 9u32.into()).unwrap()
+        );
+
+        let bytes = bincode::serialize(&ChaumPedersen {
+            alpha: BigUint::from(5u32),
+            ..cp
+        })
+        .unwrap();
+        assert!(bincode::deserialize::<ChaumPedersen>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let cp = constants();
+        let transcript = transcript(&cp);
+        let proof = cp.prove(&ChaumPedersen::generate_random_below(&cp.q));
+
+        let bytes = bincode::serialize(&cp).unwrap();
+        assert_eq!(bincode::deserialize::<ChaumPedersen>(&bytes).unwrap(), cp);
+
+        let bytes = bincode::serialize(&transcript).unwrap();
+        assert_eq!(
+            bincode::deserialize::<InteractiveTranscript>(&bytes).unwrap(),
+            transcript
+        );
+
+        // each integer is its big-endian bytes behind a u64 length
+        let bytes = bincode::serialize(&proof).unwrap();
+        let expected: usize = [&proof.r1, &proof.r2, &proof.s]
+            .iter()
+            .map(|value| 8 + value.to_bytes_be().len())
+            .sum();
+        assert_eq!(bytes.len(), expected);
+        assert_eq!(bincode::deserialize::<Proof>(&bytes).unwrap(), proof);
+    }
+
+    #[cfg(feature = "constant-time")]
+    #[test]
+    fn test_fixed_width_backend() {
+        let cp = constants();
+        let json = serde_json::to_string(&cp).unwrap();
+
+        let fixed: ChaumPedersen<crypto_bigint::U1024> = serde_json::from_str(&json).unwrap();
+        assert_eq!(Ok(fixed), cp.with_backend());
+        assert!(serde_json::from_str::<ChaumPedersen<crypto_bigint::U512>>(&json).is_err());
+    }
+}
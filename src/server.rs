@@ -1,39 +1,243 @@
 use nanoid::nanoid;
-use nmc_solution::ChaumPedersen;
+use nmc_solution::{
+    blind,
+    blind::BlindSigner,
+    designated,
+    designated::DesignatedProof,
+    groups::GroupId,
+    kdf::Kdf,
+    migrate,
+    migrate::MigrationProof,
+    schnorr::Signature,
+    session::{Challenged, Verifier},
+    sigma::{AnyOf, FiatShamir},
+    ChaumPedersen, Error,
+};
 use num_bigint::BigUint;
-use std::{collections::HashMap, sync::Mutex};
-use tonic::{transport::Server, Code, Request, Response, Status};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+use tonic::{transport::Server, Request, Response, Status};
 
-pub mod nillion {
-    include!("./nillion.rs");
-}
+mod proto;
 
-use nillion::{
+use proto::kdf_from_proto;
+use proto::nillion::{
     auth_server::{Auth, AuthServer},
+    AnonymousAuthenticationRequest, AnonymousChallengeRequest, AnonymousChallengeResponse,
     AuthenticationAnswerRequest, AuthenticationAnswerResponse, AuthenticationChallengeRequest,
-    AuthenticationChallengeResponse, RegisterRequest, RegisterResponse,
+    AuthenticationChallengeResponse, BlindIssuanceRequest, BlindIssuanceResponse, BlindSignRequest,
+    BlindSignResponse, DesignatedAuthenticationRequest, DesignatedChallengeRequest,
+    DesignatedChallengeResponse, GroupParameters, IssuerKeyRequest, IssuerKeyResponse,
+    MigrateUserRequest, MigrateUserResponse, ParametersRequest, ParametersResponse,
+    RedeemTokenRequest, RedeemTokenResponse, RegisterRequest, RegisterResponse,
+    UserParametersRequest, UserParametersResponse,
 };
 
-#[derive(Debug, Default)]
+/// The (y1, y2) of every user in a group
+type Ring = Vec<(BigUint, BigUint)>;
+
+/// The user, their group and the verifier awaiting s
+type OpenLogin = (String, GroupId, Verifier<Challenged>);
+
+#[derive(Debug)]
 struct AuthImpl {
     pub user_info: Mutex<HashMap<String, UserInfo>>,
-    pub auth_id_to_user: Mutex<HashMap<String, String>>,
+    pub verifiers: Mutex<HashMap<String, Pending<OpenLogin>>>, // Open challenges by auth_id
+    pub sessions: Mutex<HashMap<String, Session>>,
+    pub verifier_secrets: HashMap<GroupId, BigUint>, // Designated-verifier key w, yv = alpha^w
+    pub designated_auth_id_to_user: Mutex<HashMap<String, Pending<String>>>,
+    pub anonymous_rings: Mutex<HashMap<String, Pending<(GroupId, Ring)>>>, // Rings by auth_id
+    pub issuer_secrets: HashMap<GroupId, BigUint>, // Blind signing key x, issuer key y = alpha^x
+    pub issuances: Mutex<HashMap<GroupId, Issuance>>, // At most one open signer per issuer key
+    pub redeemed_tokens: Mutex<HashSet<Vec<u8>>>,
+    pub migrate_to: GroupId, // Group users in a weaker one are asked to move to
+}
+
+impl Default for AuthImpl {
+    fn default() -> Self {
+        // one key of each kind per served group
+        let secrets = || {
+            GroupId::ALL
+                .iter()
+                .map(|id| {
+                    (
+                        *id,
+                        ChaumPedersen::generate_random_below(&id.parameters().q),
+                    )
+                })
+                .collect()
+        };
+
+        AuthImpl {
+            user_info: Default::default(),
+            verifiers: Default::default(),
+            sessions: Default::default(),
+            verifier_secrets: secrets(),
+            designated_auth_id_to_user: Default::default(),
+            anonymous_rings: Default::default(),
+            issuer_secrets: secrets(),
+            issuances: Default::default(),
+            redeemed_tokens: Default::default(),
+            migrate_to: GroupId::Ffdhe4096, // the strongest group
+        }
+    }
 }
 
 #[derive(Debug, Default)]
+pub struct Session {
+    pub username: Option<String>,    // None for anonymous logins
+    pub token_issued: bool,          // At most one blind token per session, set once signed
+    pub issuances_opened: u32,       // Blind issuances opened, signed or not
+    pub upgrade_to: Option<GroupId>, // Group the user was asked to migrate to
+}
+
+/// Challenges left unanswered this long are dropped
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An open challenge and when it was issued
+#[derive(Debug)]
+pub struct Pending<T> {
+    pub value: T,
+    pub opened: Instant,
+}
+
+impl<T> Pending<T> {
+    fn is_live(&self) -> bool {
+        self.opened.elapsed() < CHALLENGE_TIMEOUT
+    }
+
+    /// The value, unless the challenge has expired
+    fn live(self) -> Option<T> {
+        self.is_live().then_some(self.value)
+    }
+}
+
+/// Opens a challenge under `auth_id`, first dropping the expired ones so
+/// that unanswered challenges do not pile up
+fn open<T>(pending: &mut HashMap<String, Pending<T>>, auth_id: String, value: T) {
+    pending.retain(|_, open| open.is_live());
+    pending.insert(
+        auth_id,
+        Pending {
+            value,
+            opened: Instant::now(),
+        },
+    );
+}
+
+/// Concurrent blind Schnorr sessions under one key are open to the ROS
+/// attack, so each issuer key has a single open issuance, and one left
+/// unanswered this long gives way to the next
+const ISSUANCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Issuances one session may open, so a client that never answers holds an
+/// issuer key for at most this many timeouts per login
+const MAX_ISSUANCES_PER_SESSION: u32 = 3;
+
+/// Fewest distinct keys an anonymous login ring may hold, matching what the
+/// client requires before it proves membership
+const MIN_RING_SIZE: usize = 3;
+
+#[derive(Debug)]
+pub struct Issuance {
+    pub issuance_id: String,
+    pub session_id: String, // Session that opened it, the only one it signs for
+    pub signer: BlindSigner,
+    pub opened: Instant,
+}
+
+impl Issuance {
+    fn is_live(&self) -> bool {
+        self.opened.elapsed() < ISSUANCE_TIMEOUT
+    }
+}
+
+#[derive(Debug)]
 pub struct UserInfo {
     pub username: String, // Registered Username String
     pub y1: BigUint,      // Registered secret y1
     pub y2: BigUint,      // Registered secret y2
-    pub r1: BigUint,
-    pub r2: BigUint,
-    pub challenge: BigUint,
-    pub solution: BigUint,
+    pub group: GroupId,   // Group y1 and y2 live in
+    pub kdf: Kdf,         // How the client derives x from the password
     pub session_id: String,
 }
 
+/// A request's `group_id`; every known group is served
+fn group(group_id: u32) -> Result<GroupId, Error> {
+    GroupId::try_from(group_id)
+}
+
+/// The parameters of `expected`, if the request's `group_id` names it
+fn expect_group(expected: GroupId, group_id: u32) -> Result<ChaumPedersen, Error> {
+    if group(group_id)? != expected {
+        return Err(Error::InvalidParameters(format!(
+            "group_id {} given, {} expected",
+            group_id,
+            u32::from(expected)
+        )));
+    }
+    Ok(expected.parameters())
+}
+
+/// `to` for users registered in a weaker group; x is the same integer in
+/// every group, whatever their KDF, so any of them can move
+fn upgrade(user_info: &UserInfo, to: GroupId) -> Option<GroupId> {
+    (user_info.group < to).then_some(to)
+}
+
+/// A poisoned lock becomes `Code::Internal` instead of taking the server down
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>, Error> {
+    Ok(mutex.lock()?)
+}
+
 #[tonic::async_trait]
 impl Auth for AuthImpl {
+    async fn get_parameters(
+        &self,
+        request: Request<ParametersRequest>,
+    ) -> Result<Response<ParametersResponse>, Status> {
+        println!("ParametersRequest -> {:?}", request);
+
+        let groups = GroupId::ALL
+            .iter()
+            .map(|id| {
+                let cp = id.parameters();
+                GroupParameters {
+                    group_id: (*id).into(),
+                    p: cp.encode_element(&cp.p),
+                    q: cp.encode_scalar(&cp.q),
+                    alpha: cp.encode_element(&cp.alpha),
+                    beta: cp.encode_element(&cp.beta),
+                    fingerprint: cp.fingerprint(),
+                }
+            })
+            .collect();
+
+        Ok(Response::new(ParametersResponse { groups }))
+    }
+
+    async fn get_user_parameters(
+        &self,
+        request: Request<UserParametersRequest>,
+    ) -> Result<Response<UserParametersResponse>, Status> {
+        println!("UserParametersRequest -> {:?}", request);
+
+        let username = request.into_inner().user;
+        let user_info_hashmap = &lock(&self.user_info)?;
+
+        if let Some(user_info) = user_info_hashmap.get(&username) {
+            Ok(Response::new(UserParametersResponse {
+                group_id: user_info.group.into(),
+                kdf: Some((&user_info.kdf).into()),
+            }))
+        } else {
+            Err(Error::NotFound(format!("User: {} not found!", username)).into())
+        }
+    }
+
     async fn register(
         &self,
         request: Request<RegisterRequest>,
@@ -41,23 +245,29 @@ impl Auth for AuthImpl {
         println!("RegisterRequest -> {:?}", request);
 
         let request = request.into_inner();
+        let id = group(request.group_id)?;
+        let cp = id.parameters();
         let username = request.user;
 
         println!("Processing Auth::register() for {}", username);
 
         let user_info = UserInfo {
             username: username.clone(),
-            y1: BigUint::from_bytes_be(&request.y1),
-            y2: BigUint::from_bytes_be(&request.y2),
-            ..Default::default()
+            y1: cp.decode_element(&request.y1)?,
+            y2: cp.decode_element(&request.y2)?,
+            group: id,
+            kdf: kdf_from_proto(request.kdf)?,
+            session_id: String::new(),
         };
 
-        let user_info_hashmap = &mut self.user_info.lock().unwrap();
+        let user_info_hashmap = &mut lock(&self.user_info)?;
         user_info_hashmap.insert(username.clone(), user_info);
 
         println!("✅ Successful Registration username: {:?}", username);
 
-        Ok(Response::new(RegisterResponse {}))
+        Ok(Response::new(RegisterResponse {
+            group_id: id.into(),
+        }))
     }
 
     async fn create_authentication_challenge(
@@ -73,31 +283,38 @@ impl Auth for AuthImpl {
             "Processing Auth::create_authentication_challenge() for {}",
             username
         );
-        let user_info_hashmap = &mut self.user_info.lock().unwrap();
+        let user_info_hashmap = &mut lock(&self.user_info)?;
 
         if let Some(user_info) = user_info_hashmap.get_mut(&username) {
-            let (_, _, _, q) = ChaumPedersen::get_constants();
-            let challenge = ChaumPedersen::generate_random_below(&q);
+            let cp = expect_group(user_info.group, request.group_id)?;
             let auth_id = nanoid!();
 
-            user_info.challenge = challenge.clone();
-            user_info.r1 = BigUint::from_bytes_be(&request.r1);
-            user_info.r2 = BigUint::from_bytes_be(&request.r2);
+            let verifier = Verifier::new(
+                &cp,
+                &user_info.y1,
+                &user_info.y2,
+                &cp.decode_element(&request.r1)?,
+                &cp.decode_element(&request.r2)?,
+            )
+            .challenge();
+            let challenge = verifier.challenge_value().clone();
 
             println!("[auth_id -> {}][challenge -> {}]", auth_id, challenge);
 
-            let auth_id_to_user = &mut self.auth_id_to_user.lock().unwrap();
-            auth_id_to_user.insert(auth_id.clone(), username.clone());
+            let verifiers = &mut lock(&self.verifiers)?;
+            open(
+                verifiers,
+                auth_id.clone(),
+                (username.clone(), user_info.group, verifier),
+            );
 
             Ok(Response::new(AuthenticationChallengeResponse {
                 auth_id,
-                c: challenge.to_bytes_be(),
+                c: cp.encode_scalar(&challenge),
+                group_id: user_info.group.into(),
             }))
         } else {
-            Err(Status::new(
-                Code::NotFound,
-                format!("User: {} not found!", username),
-            ))
+            Err(Error::NotFound(format!("User: {} not found!", username)).into())
         }
     }
 
@@ -115,63 +332,850 @@ impl Auth for AuthImpl {
             auth_id
         );
 
-        let auth_id_to_user_hashmap = &mut self.auth_id_to_user.lock().unwrap();
+        // An auth_id answers exactly one challenge, right or wrong
+        let verifier = lock(&self.verifiers)?
+            .remove(&auth_id)
+            .and_then(Pending::live);
 
-        if let Some(username) = auth_id_to_user_hashmap.get(&auth_id) {
-            let user_info_hashmap = &mut self.user_info.lock().unwrap();
-            let user_info = user_info_hashmap
-                .get_mut(username)
-                .expect("AuthId not found on hashmap");
+        if let Some((username, id, verifier)) = verifier {
+            let cp = expect_group(id, request.group_id)?;
+            let s = cp.decode_scalar(&request.s)?;
+            let verification = verifier.receive_response(&s).verify();
+
+            if verification {
+                let session_id = nanoid!();
+                let upgrade_to = lock(&self.user_info)?
+                    .get(&username)
+                    .and_then(|user_info| upgrade(user_info, self.migrate_to));
+
+                let sessions = &mut lock(&self.sessions)?;
+                sessions.insert(
+                    session_id.clone(),
+                    Session {
+                        username: Some(username.clone()),
+                        upgrade_to,
+                        ..Default::default()
+                    },
+                );
+
+                println!("✅ Correct Challenge Solution for username: {:?}", username);
+
+                Ok(Response::new(AuthenticationAnswerResponse {
+                    session_id,
+                    group_id: id.into(),
+                    upgrade_group_id: upgrade_to.map_or(0, u32::from),
+                }))
+            } else {
+                println!("❌ Wrong Challenge Solution for username: {:?}", username);
 
-            let s = BigUint::from_bytes_be(&request.s);
-            user_info.solution = s;
+                Err(Error::Verification(format!(
+                    "AuthId: {} bad solution to the challenge",
+                    auth_id
+                ))
+                .into())
+            }
+        } else {
+            Err(Error::NotFound(format!("AuthId: {} not found in database", auth_id)).into())
+        }
+    }
+
+    async fn create_designated_challenge(
+        &self,
+        request: Request<DesignatedChallengeRequest>,
+    ) -> Result<Response<DesignatedChallengeResponse>, Status> {
+        println!("DesignatedChallengeRequest -> {:?}", request);
+
+        let request = request.into_inner();
+        let username = request.user;
+
+        let user_info_hashmap = &lock(&self.user_info)?;
 
-            let (alpha, beta, p, q) = ChaumPedersen::get_constants();
-            let cp = ChaumPedersen { alpha, beta, p, q };
+        if let Some(user_info) = user_info_hashmap.get(&username) {
+            let cp = expect_group(user_info.group, request.group_id)?;
+            let yv = cp
+                .alpha
+                .modpow(&self.verifier_secrets[&user_info.group], &cp.p);
+            let auth_id = nanoid!();
 
-            let verification = cp.verify(
+            let auth_id_to_user = &mut lock(&self.designated_auth_id_to_user)?;
+            open(auth_id_to_user, auth_id.clone(), username);
+
+            Ok(Response::new(DesignatedChallengeResponse {
+                auth_id,
+                yv: cp.encode_element(&yv),
+                group_id: user_info.group.into(),
+            }))
+        } else {
+            Err(Error::NotFound(format!("User: {} not found!", username)).into())
+        }
+    }
+
+    async fn verify_designated_authentication(
+        &self,
+        request: Request<DesignatedAuthenticationRequest>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        println!("DesignatedAuthenticationRequest -> {:?}", request);
+
+        let request = request.into_inner();
+        let auth_id = request.auth_id;
+
+        // each auth_id is good for a single proof
+        let username = lock(&self.designated_auth_id_to_user)?
+            .remove(&auth_id)
+            .and_then(Pending::live);
+
+        if let Some(username) = username {
+            let user_info_hashmap = &lock(&self.user_info)?;
+            let user_info = user_info_hashmap
+                .get(&username)
+                .ok_or_else(|| Error::NotFound(format!("User: {} not found!", username)))?;
+            let cp = expect_group(user_info.group, request.group_id)?;
+            let yv = cp
+                .alpha
+                .modpow(&self.verifier_secrets[&user_info.group], &cp.p);
+
+            let proof = DesignatedProof {
+                r1: cp.decode_element(&request.r1)?,
+                r2: cp.decode_element(&request.r2)?,
+                c1: cp.decode_scalar(&request.c1)?,
+                s1: cp.decode_scalar(&request.s1)?,
+                t: cp.decode_element(&request.t)?,
+                c2: cp.decode_scalar(&request.c2)?,
+                s2: cp.decode_scalar(&request.s2)?,
+            };
+
+            let verification = designated::verify(
+                &cp,
                 &user_info.y1,
                 &user_info.y2,
-                &user_info.r1,
-                &user_info.r2,
-                &user_info.challenge,
-                &user_info.solution,
+                &yv,
+                auth_id.as_bytes(),
+                &proof,
             );
 
             if verification {
                 let session_id = nanoid!();
 
-                println!("✅ Correct Challenge Solution for username: {:?}", username);
+                let sessions = &mut lock(&self.sessions)?;
+                sessions.insert(
+                    session_id.clone(),
+                    Session {
+                        username: Some(username.clone()),
+                        ..Default::default()
+                    },
+                );
+
+                println!("✅ Correct Designated Proof for username: {:?}", username);
 
-                Ok(Response::new(AuthenticationAnswerResponse { session_id }))
+                Ok(Response::new(AuthenticationAnswerResponse {
+                    session_id,
+                    group_id: user_info.group.into(),
+                    upgrade_group_id: 0,
+                }))
             } else {
-                println!("❌ Wrong Challenge Solution for username: {:?}", username);
+                println!("❌ Wrong Designated Proof for username: {:?}", username);
 
-                Err(Status::new(
-                    Code::PermissionDenied,
-                    format!("AuthId: {} bad solution to the challenge", auth_id),
+                Err(Error::Verification(format!("AuthId: {} bad designated proof", auth_id)).into())
+            }
+        } else {
+            Err(Error::NotFound(format!("AuthId: {} not found in database", auth_id)).into())
+        }
+    }
+
+    async fn create_anonymous_challenge(
+        &self,
+        request: Request<AnonymousChallengeRequest>,
+    ) -> Result<Response<AnonymousChallengeResponse>, Status> {
+        println!("AnonymousChallengeRequest -> {:?}", request);
+
+        let id = group(request.into_inner().group_id)?;
+        let cp = id.parameters();
+        let user_info_hashmap = &lock(&self.user_info)?;
+
+        // the ring is every distinct key registered in the requested group,
+        // in key order, so it leaks neither usernames nor insertion history
+        let ring: Ring = user_info_hashmap
+            .values()
+            .filter(|user_info| user_info.group == id)
+            .map(|user_info| (user_info.y1.clone(), user_info.y2.clone()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        if ring.len() < MIN_RING_SIZE {
+            return Err(Error::FailedPrecondition(format!(
+                "{} distinct users in group_id {}, an anonymous login needs {}",
+                ring.len(),
+                u32::from(id),
+                MIN_RING_SIZE
+            ))
+            .into());
+        }
+
+        let auth_id = nanoid!();
+        let response = AnonymousChallengeResponse {
+            auth_id: auth_id.clone(),
+            y1: ring.iter().map(|(y1, _)| cp.encode_element(y1)).collect(),
+            y2: ring.iter().map(|(_, y2)| cp.encode_element(y2)).collect(),
+            group_id: id.into(),
+        };
+
+        let anonymous_rings = &mut lock(&self.anonymous_rings)?;
+        open(anonymous_rings, auth_id, (id, ring));
+
+        Ok(Response::new(response))
+    }
+
+    async fn verify_anonymous_authentication(
+        &self,
+        request: Request<AnonymousAuthenticationRequest>,
+    ) -> Result<Response<AuthenticationAnswerResponse>, Status> {
+        println!("AnonymousAuthenticationRequest -> {:?}", request);
+
+        let request = request.into_inner();
+        let auth_id = request.auth_id;
+
+        // each auth_id is good for a single proof
+        let ring = lock(&self.anonymous_rings)?
+            .remove(&auth_id)
+            .and_then(Pending::live);
+
+        if let Some((id, ring)) = ring {
+            let cp = expect_group(id, request.group_id)?;
+            let elements = |values: &[Vec<u8>]| -> Result<Vec<BigUint>, Error> {
+                values.iter().map(|v| cp.decode_element(v)).collect()
+            };
+            let scalars = |values: &[Vec<u8>]| -> Result<Vec<BigUint>, Error> {
+                values.iter().map(|v| cp.decode_scalar(v)).collect()
+            };
+            let commitments = elements(&request.r1)?
+                .into_iter()
+                .zip(elements(&request.r2)?)
+                .collect();
+            let responses = scalars(&request.c)?
+                .into_iter()
+                .zip(scalars(&request.s)?)
+                .collect();
+
+            let verification = request.r1.len() == request.r2.len()
+                && request.c.len() == request.s.len()
+                && FiatShamir(AnyOf(cp)).verify(
+                    &ring,
+                    auth_id.as_bytes(),
+                    &(commitments, responses),
+                );
+
+            if verification {
+                let session_id = nanoid!();
+
+                let sessions = &mut lock(&self.sessions)?;
+                sessions.insert(session_id.clone(), Session::default());
+
+                println!("✅ Correct Anonymous Proof over {} users", ring.len());
+
+                Ok(Response::new(AuthenticationAnswerResponse {
+                    session_id,
+                    group_id: id.into(),
+                    upgrade_group_id: 0,
+                }))
+            } else {
+                println!("❌ Wrong Anonymous Proof over {} users", ring.len());
+
+                Err(Error::Verification(format!("AuthId: {} bad anonymous proof", auth_id)).into())
+            }
+        } else {
+            Err(Error::NotFound(format!("AuthId: {} not found in database", auth_id)).into())
+        }
+    }
+
+    async fn get_issuer_key(
+        &self,
+        request: Request<IssuerKeyRequest>,
+    ) -> Result<Response<IssuerKeyResponse>, Status> {
+        println!("IssuerKeyRequest -> {:?}", request);
+
+        let id = group(request.into_inner().group_id)?;
+        let cp = id.parameters();
+        let y = cp.alpha.modpow(&self.issuer_secrets[&id], &cp.p);
+
+        Ok(Response::new(IssuerKeyResponse {
+            y: cp.encode_element(&y),
+            group_id: id.into(),
+        }))
+    }
+
+    async fn create_blind_issuance(
+        &self,
+        request: Request<BlindIssuanceRequest>,
+    ) -> Result<Response<BlindIssuanceResponse>, Status> {
+        println!("BlindIssuanceRequest -> {:?}", request);
+
+        let request = request.into_inner();
+        let id = group(request.group_id)?;
+        let cp = id.parameters();
+        let session_id = request.session_id;
+
+        let sessions = &mut lock(&self.sessions)?;
+
+        if let Some(session) = sessions.get_mut(&session_id) {
+            if session.token_issued {
+                return Err(Error::FailedPrecondition(format!(
+                    "SessionId: {} already received a token",
+                    session_id
                 ))
+                .into());
             }
+            if session.issuances_opened >= MAX_ISSUANCES_PER_SESSION {
+                return Err(Error::FailedPrecondition(format!(
+                    "SessionId: {} opened {} issuances without finishing one, log in again",
+                    session_id, MAX_ISSUANCES_PER_SESSION
+                ))
+                .into());
+            }
+
+            let issuances = &mut lock(&self.issuances)?;
+            // the same session asking again abandons its own issuance
+            if issuances
+                .get(&id)
+                .is_some_and(|open| open.is_live() && open.session_id != session_id)
+            {
+                return Err(Error::FailedPrecondition(format!(
+                    "Another blind issuance is open for group_id {}, retry shortly",
+                    u32::from(id)
+                ))
+                .into());
+            }
+
+            let (signer, r) = BlindSigner::new(&cp);
+            let issuance_id = nanoid!();
+
+            // replaces a stale or abandoned issuance, if any
+            issuances.insert(
+                id,
+                Issuance {
+                    issuance_id: issuance_id.clone(),
+                    session_id: session_id.clone(),
+                    signer,
+                    opened: Instant::now(),
+                },
+            );
+            session.issuances_opened += 1;
+
+            println!(
+                "Blind issuance {} for username: {:?}",
+                issuance_id, session.username
+            );
+
+            Ok(Response::new(BlindIssuanceResponse {
+                issuance_id,
+                r: cp.encode_element(&r),
+                group_id: id.into(),
+            }))
         } else {
-            Err(Status::new(
-                Code::NotFound,
-                format!("AuthId: {} not found in database", auth_id),
+            Err(Error::Unauthenticated(format!("SessionId: {} not found", session_id)).into())
+        }
+    }
+
+    async fn blind_sign(
+        &self,
+        request: Request<BlindSignRequest>,
+    ) -> Result<Response<BlindSignResponse>, Status> {
+        println!("BlindSignRequest -> {:?}", request);
+
+        let request = request.into_inner();
+        let id = group(request.group_id)?;
+        let cp = id.parameters();
+        let issuance_id = request.issuance_id;
+
+        let sessions = &mut lock(&self.sessions)?;
+        let issuances = &mut lock(&self.issuances)?;
+
+        let open = match issuances.remove(&id) {
+            Some(open) if open.issuance_id == issuance_id => open,
+            other => {
+                // another session's issuance stays open
+                if let Some(other) = other {
+                    issuances.insert(id, other);
+                }
+                return Err(
+                    Error::NotFound(format!("IssuanceId: {} not found", issuance_id)).into(),
+                );
+            }
+        };
+
+        if !open.is_live() {
+            return Err(Error::NotFound(format!("IssuanceId: {} expired", issuance_id)).into());
+        }
+
+        let session = sessions.get_mut(&open.session_id).ok_or_else(|| {
+            Error::Unauthenticated(format!("SessionId: {} not found", open.session_id))
+        })?;
+        if session.token_issued {
+            return Err(Error::FailedPrecondition(format!(
+                "SessionId: {} already received a token",
+                open.session_id
             ))
+            .into());
+        }
+
+        let e = cp.decode_scalar(&request.e)?;
+        let s = open.signer.sign(&cp, &self.issuer_secrets[&id], &e);
+
+        // only a signed answer uses up the session's token
+        session.token_issued = true;
+
+        Ok(Response::new(BlindSignResponse {
+            s: cp.encode_scalar(&s),
+            group_id: id.into(),
+        }))
+    }
+
+    async fn redeem_token(
+        &self,
+        request: Request<RedeemTokenRequest>,
+    ) -> Result<Response<RedeemTokenResponse>, Status> {
+        println!("RedeemTokenRequest -> {:?}", request);
+
+        let request = request.into_inner();
+        let id = group(request.group_id)?;
+        let cp = id.parameters();
+        let y = cp.alpha.modpow(&self.issuer_secrets[&id], &cp.p);
+
+        let signature = Signature {
+            r: cp.decode_element(&request.r)?,
+            s: cp.decode_scalar(&request.s)?,
+        };
+
+        if !blind::verify(&cp, &y, &request.message, &signature) {
+            return Err(Error::Verification("Token signature is not valid".into()).into());
+        }
+
+        let redeemed_tokens = &mut lock(&self.redeemed_tokens)?;
+        if !redeemed_tokens.insert(request.message) {
+            return Err(Error::AlreadyExists("Token was already redeemed".into()).into());
+        }
+
+        println!("✅ Redeemed anonymous token");
+
+        Ok(Response::new(RedeemTokenResponse {
+            group_id: id.into(),
+        }))
+    }
+
+    async fn migrate_user(
+        &self,
+        request: Request<MigrateUserRequest>,
+    ) -> Result<Response<MigrateUserResponse>, Status> {
+        println!("MigrateUserRequest -> {:?}", request);
+
+        let request = request.into_inner();
+        let session_id = request.session_id;
+
+        // each login is good for a single attempt, right or wrong
+        let (username, to) = {
+            let sessions = &mut lock(&self.sessions)?;
+            let session = sessions.get_mut(&session_id).ok_or_else(|| {
+                Error::Unauthenticated(format!("SessionId: {} not found", session_id))
+            })?;
+            match (&session.username, session.upgrade_to.take()) {
+                (Some(username), Some(to)) => (username.clone(), to),
+                _ => {
+                    return Err(Error::FailedPrecondition(format!(
+                        "SessionId: {} has no migration pending",
+                        session_id
+                    ))
+                    .into())
+                }
+            }
+        };
+        let to_cp = expect_group(to, request.group_id)?;
+
+        let (from, y1_old, y2_old) = {
+            let user_info_hashmap = &lock(&self.user_info)?;
+            let user_info = user_info_hashmap
+                .get(&username)
+                .ok_or_else(|| Error::NotFound(format!("User: {} not found!", username)))?;
+            (user_info.group, user_info.y1.clone(), user_info.y2.clone())
+        };
+        if from == to {
+            return Err(Error::FailedPrecondition(format!(
+                "User: {} is already in group_id {}",
+                username,
+                u32::from(to)
+            ))
+            .into());
+        }
+        let from_cp = from.parameters();
+
+        let rounds = [
+            &request.r1,
+            &request.r2,
+            &request.t1,
+            &request.t2,
+            &request.s,
+        ];
+        if rounds.iter().any(|values| values.len() != migrate::ROUNDS) {
+            return Err(Error::Decode(format!(
+                "migration proof needs {} of each of r1, r2, t1, t2 and s",
+                migrate::ROUNDS
+            ))
+            .into());
+        }
+        let pairs = |cp: &ChaumPedersen, first: &[Vec<u8>], second: &[Vec<u8>]| {
+            first
+                .iter()
+                .zip(second)
+                .map(|(first, second)| {
+                    Ok((
+                        migrate::decode_commitment(cp, first)?,
+                        migrate::decode_commitment(cp, second)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()
+        };
+
+        let y1 = to_cp.decode_element(&request.y1)?;
+        let y2 = to_cp.decode_element(&request.y2)?;
+        let proof = MigrationProof {
+            r: pairs(&from_cp, &request.r1, &request.r2)?,
+            t: pairs(&to_cp, &request.t1, &request.t2)?,
+            s: request
+                .s
+                .iter()
+                .map(|s| migrate::decode_response(s))
+                .collect::<Result<_, _>>()?,
+        };
+
+        // verified without the lock, which would hold up every login meanwhile
+        let verification = migrate::verify(
+            &from_cp,
+            &to_cp,
+            &y1_old,
+            &y2_old,
+            &y1,
+            &y2,
+            session_id.as_bytes(),
+            &proof,
+        );
+
+        if verification {
+            let user_info_hashmap = &mut lock(&self.user_info)?;
+            let user_info = user_info_hashmap
+                .get_mut(&username)
+                .filter(|user_info| user_info.group == from && user_info.y1 == y1_old)
+                .ok_or_else(|| {
+                    Error::FailedPrecondition(format!(
+                        "User: {} changed during the migration",
+                        username
+                    ))
+                })?;
+
+            println!(
+                "✅ Migrated username: {:?} from group_id {} to {}",
+                username,
+                u32::from(from),
+                u32::from(to)
+            );
+
+            user_info.y1 = y1;
+            user_info.y2 = y2;
+            user_info.group = to;
+
+            Ok(Response::new(MigrateUserResponse {
+                group_id: to.into(),
+            }))
+        } else {
+            println!("❌ Wrong Migration Proof for username: {:?}", username);
+
+            Err(
+                Error::Verification(format!("SessionId: {} bad migration proof", session_id))
+                    .into(),
+            )
         }
     }
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<(), Error> {
     let addr = "0.0.0.0:50051".to_string();
 
     println!("Building the server at {}", addr);
 
     let auth_impl = AuthImpl::default();
 
+    // gRPC-web as well, for the browser prover in the wasm bindings
     Server::builder()
-        .add_service(AuthServer::new(auth_impl))
-        .serve(addr.parse().expect("could not parse address"))
-        .await
-        .unwrap();
+        .accept_http1(true)
+        .add_service(tonic_web::enable(AuthServer::new(auth_impl)))
+        .serve(
+            addr.parse()
+                .map_err(|_| Error::Decode(format!("could not parse address {}", addr)))?,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nmc_solution::session::Prover;
+    use tonic::Code;
+
+    /// Logs `user` in to the group the server has on record with x
+    async fn login(server: &AuthImpl, user: &str, x: &BigUint) -> AuthenticationAnswerResponse {
+        let recorded = server
+            .get_user_parameters(Request::new(UserParametersRequest { user: user.into() }))
+            .await
+            .unwrap()
+            .into_inner();
+        let cp = GroupId::try_from(recorded.group_id).unwrap().parameters();
+
+        let prover = Prover::commit(&cp, x);
+        let (r1, r2) = prover.commitment();
+        let challenge = server
+            .create_authentication_challenge(Request::new(AuthenticationChallengeRequest {
+                user: user.into(),
+                r1: cp.encode_element(r1),
+                r2: cp.encode_element(r2),
+                group_id: recorded.group_id,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let c = cp.decode_scalar(&challenge.c).unwrap();
+        let prover = prover.receive_challenge(&c).respond();
+        server
+            .verify_authentication(Request::new(AuthenticationAnswerRequest {
+                auth_id: challenge.auth_id,
+                s: cp.encode_scalar(prover.response()),
+                group_id: recorded.group_id,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_parameters() {
+        let response = AuthImpl::default()
+            .get_parameters(Request::new(ParametersRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.groups.len(), GroupId::ALL.len());
+        for offered in response.groups {
+            let cp = GroupId::try_from(offered.group_id).unwrap().parameters();
+            assert_eq!(offered.p.len(), cp.element_len());
+            assert_eq!(offered.q.len(), cp.scalar_len());
+            assert_eq!(BigUint::from_bytes_be(&offered.p), cp.p);
+            assert_eq!(cp.decode_element(&offered.alpha), Ok(cp.alpha.clone()));
+            assert_eq!(cp.decode_element(&offered.beta), Ok(cp.beta));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_user() {
+        let (from, to) = (GroupId::Rfc5114, GroupId::Ffdhe2048);
+        let (from_cp, to_cp) = (from.parameters(), to.parameters());
+        let server = AuthImpl {
+            migrate_to: to,
+            ..Default::default()
+        };
+
+        // registered by nmc-client, with PBKDF2, in a weaker group
+        let kdf = Kdf::Pbkdf2Sha256 {
+            salt: vec![7; Kdf::SALT_LEN],
+            iterations: Kdf::MIN_ITERATIONS,
+        };
+        let x = kdf.derive(b"password");
+        let (y1, y2) = from_cp.compute_pair(&x);
+        server
+            .register(Request::new(RegisterRequest {
+                user: "alice".into(),
+                y1: from_cp.encode_element(&y1),
+                y2: from_cp.encode_element(&y2),
+                group_id: from.into(),
+                kdf: Some((&kdf).into()),
+            }))
+            .await
+            .unwrap();
+
+        let session = login(&server, "alice", &x).await;
+        assert_eq!(session.upgrade_group_id, u32::from(to));
+
+        // a response one byte short is refused, and uses up the session's attempt
+        let element = to_cp.encode_element(&to_cp.alpha);
+        let short = MigrateUserRequest {
+            session_id: session.session_id.clone(),
+            y1: element.clone(),
+            y2: element.clone(),
+            r1: vec![from_cp.encode_element(&from_cp.alpha); migrate::ROUNDS],
+            r2: vec![from_cp.encode_element(&from_cp.beta); migrate::ROUNDS],
+            t1: vec![element.clone(); migrate::ROUNDS],
+            t2: vec![element; migrate::ROUNDS],
+            s: vec![vec![0; migrate::RESPONSE_LEN - 1]; migrate::ROUNDS],
+            group_id: to.into(),
+        };
+        let status = server
+            .migrate_user(Request::new(short.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        let status = server.migrate_user(Request::new(short)).await.unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+
+        // the same x, proven to be, moves to the new group
+        let session = login(&server, "alice", &x).await;
+        let proof = migrate::prove(&from_cp, &to_cp, &x, session.session_id.as_bytes()).unwrap();
+        let (y1, y2) = to_cp.compute_pair(&x);
+        let migrated = server
+            .migrate_user(Request::new(MigrateUserRequest {
+                session_id: session.session_id,
+                y1: to_cp.encode_element(&y1),
+                y2: to_cp.encode_element(&y2),
+                r1: proof
+                    .r
+                    .iter()
+                    .map(|(r1, _)| from_cp.encode_element(r1))
+                    .collect(),
+                r2: proof
+                    .r
+                    .iter()
+                    .map(|(_, r2)| from_cp.encode_element(r2))
+                    .collect(),
+                t1: proof
+                    .t
+                    .iter()
+                    .map(|(t1, _)| to_cp.encode_element(t1))
+                    .collect(),
+                t2: proof
+                    .t
+                    .iter()
+                    .map(|(_, t2)| to_cp.encode_element(t2))
+                    .collect(),
+                s: proof.s.iter().map(migrate::encode_response).collect(),
+                group_id: to.into(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(migrated.group_id, u32::from(to));
+
+        // and logs in there with the same password and KDF
+        let recorded = server
+            .get_user_parameters(Request::new(UserParametersRequest {
+                user: "alice".into(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(recorded.group_id, u32::from(to));
+        assert_eq!(kdf_from_proto(recorded.kdf).unwrap(), kdf);
+
+        let session = login(&server, "alice", &x).await;
+        assert_eq!(session.group_id, u32::from(to));
+        assert_eq!(session.upgrade_group_id, 0);
+    }
+
+    /// The status of a call expected to fail
+    fn code<T>(result: Result<T, Status>) -> Code {
+        result.map(|_| ()).unwrap_err().code()
+    }
+
+    #[tokio::test]
+    async fn test_blind_issuance() {
+        let id = GroupId::Rfc5114;
+        let cp = id.parameters();
+        let server = AuthImpl::default();
+        for session_id in ["alice", "bob", "carol"] {
+            lock(&server.sessions)
+                .unwrap()
+                .insert(session_id.into(), Session::default());
+        }
+        let open = |session_id: &str| {
+            server.create_blind_issuance(Request::new(BlindIssuanceRequest {
+                session_id: session_id.into(),
+                group_id: id.into(),
+            }))
+        };
+        let sign = |issuance_id: String, e: Vec<u8>| {
+            server.blind_sign(Request::new(BlindSignRequest {
+                issuance_id,
+                e,
+                group_id: id.into(),
+            }))
+        };
+
+        // one open issuance per key; its session may abandon it for a new one
+        open("alice").await.unwrap();
+        assert_eq!(code(open("bob").await), Code::FailedPrecondition);
+        let issuance = open("alice").await.unwrap().into_inner();
+
+        // a failed signing call does not use up the token
+        assert_eq!(
+            code(sign(issuance.issuance_id, vec![0; 3]).await),
+            Code::InvalidArgument
+        );
+        assert!(!lock(&server.sessions).unwrap()["alice"].token_issued);
+
+        let issuance = open("alice").await.unwrap().into_inner();
+        let e = cp.encode_scalar(&BigUint::from(7u32));
+        sign(issuance.issuance_id, e.clone()).await.unwrap();
+        assert!(lock(&server.sessions).unwrap()["alice"].token_issued);
+        assert_eq!(code(open("alice").await), Code::FailedPrecondition);
+
+        // a session that never answers holds the key a bounded number of times
+        for _ in 0..MAX_ISSUANCES_PER_SESSION {
+            open("bob").await.unwrap();
+        }
+        assert_eq!(code(open("bob").await), Code::FailedPrecondition);
+        assert!(!lock(&server.sessions).unwrap()["bob"].token_issued);
+
+        // and gives way to the next once its last one expires
+        assert_eq!(code(open("carol").await), Code::FailedPrecondition);
+        if let Some(stale) = lock(&server.issuances).unwrap().get_mut(&id) {
+            stale.opened -= ISSUANCE_TIMEOUT;
+        }
+        let issuance = open("carol").await.unwrap().into_inner();
+        sign(issuance.issuance_id, e).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_anonymous_ring() {
+        let id = GroupId::Rfc5114;
+        let cp = id.parameters();
+        let server = AuthImpl::default();
+        let register = |user: &str, x: u32| {
+            let (y1, y2) = cp.compute_pair(&BigUint::from(x));
+            server.register(Request::new(RegisterRequest {
+                user: user.into(),
+                y1: cp.encode_element(&y1),
+                y2: cp.encode_element(&y2),
+                group_id: id.into(),
+                kdf: None,
+            }))
+        };
+        let challenge = || {
+            server.create_anonymous_challenge(Request::new(AnonymousChallengeRequest {
+                group_id: id.into(),
+            }))
+        };
+
+        register("alice", 1).await.unwrap();
+        register("bob", 2).await.unwrap();
+        assert_eq!(code(challenge().await), Code::FailedPrecondition);
+
+        // a copy of alice's key does not make the ring any larger
+        register("carol", 1).await.unwrap();
+        assert_eq!(code(challenge().await), Code::FailedPrecondition);
+
+        register("dave", 3).await.unwrap();
+        let ring = challenge().await.unwrap().into_inner();
+        assert_eq!(ring.y1.len(), MIN_RING_SIZE);
+        let distinct: HashSet<_> = ring.y1.iter().zip(&ring.y2).collect();
+        assert_eq!(distinct.len(), MIN_RING_SIZE);
+    }
 }
@@ -0,0 +1,253 @@
+//! Typestate prover and verifier for the interactive login protocol.
+//!
+//! Both sides move Committed -> Challenged -> Responded by value, so the
+//! compiler rejects answering a challenge twice, reusing the nonce k of a
+//! finished session, or verifying before a challenge has been issued.
+//!
+//! Answering twice uses a moved prover:
+//!
+//! ```compile_fail
+//! use nmc_solution::{groups::GroupId, session::Prover};
+//! use num_bigint::BigUint;
+//!
+//! let cp = GroupId::Rfc5114.parameters();
+//! let prover = Prover::commit(&cp, &BigUint::from(7u32)).receive_challenge(&BigUint::from(1u32));
+//! let first = prover.respond();
+//! let second = prover.respond();
+//! ```
+//!
+//! A prover cannot respond before it has a challenge:
+//!
+//! ```compile_fail
+//! use nmc_solution::{groups::GroupId, session::Prover};
+//! use num_bigint::BigUint;
+//!
+//! let cp = GroupId::Rfc5114.parameters();
+//! let prover = Prover::commit(&cp, &BigUint::from(7u32)).respond();
+//! ```
+//!
+//! Nor can a verifier check an answer it has not received:
+//!
+//! ```compile_fail
+//! use nmc_solution::{groups::GroupId, session::Verifier};
+//! use num_bigint::BigUint;
+//!
+//! let cp = GroupId::Rfc5114.parameters();
+//! let one = BigUint::from(1u32);
+//! let verifier = Verifier::new(&cp, &one, &one, &one, &one).challenge();
+//! verifier.verify();
+//! ```
+
+use crate::ChaumPedersen;
+use core::fmt;
+use num_bigint::BigUint;
+use std::marker::PhantomData;
+
+/// (r1, r2) = compute_pair(k) has been sent / received
+#[derive(Debug)]
+pub struct Committed;
+
+/// c has been received / issued
+#[derive(Debug)]
+pub struct Challenged;
+
+/// s = k - c * x mod q has been sent / received
+#[derive(Debug)]
+pub struct Responded;
+
+pub struct Prover<S> {
+    cp: ChaumPedersen,
+    x: BigUint,
+    k: BigUint,
+    r1: BigUint,
+    r2: BigUint,
+    c: BigUint,
+    s: BigUint,
+    state: PhantomData<S>,
+}
+
+/// x and k stay out of logs
+impl<S> fmt::Debug for Prover<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prover")
+            .field("cp", &self.cp)
+            .field("x", &"<redacted>")
+            .field("k", &"<redacted>")
+            .field("r1", &self.r1)
+            .field("r2", &self.r2)
+            .field("c", &self.c)
+            .field("s", &self.s)
+            .finish()
+    }
+}
+
+impl<S> Prover<S> {
+    fn into_state<T>(self) -> Prover<T> {
+        Prover {
+            cp: self.cp,
+            x: self.x,
+            k: self.k,
+            r1: self.r1,
+            r2: self.r2,
+            c: self.c,
+            s: self.s,
+            state: PhantomData,
+        }
+    }
+
+    pub fn commitment(&self) -> (&BigUint, &BigUint) {
+        (&self.r1, &self.r2)
+    }
+}
+
+impl Prover<Committed> {
+    /// Draws a fresh nonce k; the only way to obtain a prover
+    pub fn commit(cp: &ChaumPedersen, x: &BigUint) -> Self {
+        let k = ChaumPedersen::generate_random_below(&cp.q);
+        let (r1, r2) = cp.compute_pair(&k);
+
+        Prover {
+            cp: cp.clone(),
+            x: x.clone(),
+            k,
+            r1,
+            r2,
+            c: BigUint::default(),
+            s: BigUint::default(),
+            state: PhantomData,
+        }
+    }
+
+    pub fn receive_challenge(mut self, c: &BigUint) -> Prover<Challenged> {
+        self.c = c % &self.cp.q;
+        self.into_state()
+    }
+}
+
+impl Prover<Challenged> {
+    /// s = k - c * x mod q; the nonce is erased afterwards
+    pub fn respond(mut self) -> Prover<Responded> {
+        self.s = self.cp.solve(&self.k, &self.c, &self.x);
+        self.k = BigUint::default();
+        self.into_state()
+    }
+}
+
+impl Prover<Responded> {
+    pub fn response(&self) -> &BigUint {
+        &self.s
+    }
+}
+
+/// Verifier for a registered (y1, y2)
+#[derive(Debug)]
+pub struct Verifier<S> {
+    cp: ChaumPedersen,
+    y1: BigUint,
+    y2: BigUint,
+    r1: BigUint,
+    r2: BigUint,
+    c: BigUint,
+    s: BigUint,
+    state: PhantomData<S>,
+}
+
+impl<S> Verifier<S> {
+    fn into_state<T>(self) -> Verifier<T> {
+        Verifier {
+            cp: self.cp,
+            y1: self.y1,
+            y2: self.y2,
+            r1: self.r1,
+            r2: self.r2,
+            c: self.c,
+            s: self.s,
+            state: PhantomData,
+        }
+    }
+}
+
+impl Verifier<Committed> {
+    /// Starts from the prover's commitment
+    pub fn new(cp: &ChaumPedersen, y1: &BigUint, y2: &BigUint, r1: &BigUint, r2: &BigUint) -> Self {
+        Verifier {
+            cp: cp.clone(),
+            y1: y1.clone(),
+            y2: y2.clone(),
+            r1: r1.clone(),
+            r2: r2.clone(),
+            c: BigUint::default(),
+            s: BigUint::default(),
+            state: PhantomData,
+        }
+    }
+
+    /// Draws c uniformly from Z_q
+    pub fn challenge(mut self) -> Verifier<Challenged> {
+        self.c = ChaumPedersen::generate_random_below(&self.cp.q);
+        self.into_state()
+    }
+}
+
+impl Verifier<Challenged> {
+    pub fn challenge_value(&self) -> &BigUint {
+        &self.c
+    }
+
+    pub fn receive_response(mut self, s: &BigUint) -> Verifier<Responded> {
+        self.s = s.clone();
+        self.into_state()
+    }
+}
+
+impl Verifier<Responded> {
+    /// r1 == alpha^s * y1^c and r2 == beta^s * y2^c
+    pub fn verify(&self) -> bool {
+        self.cp
+            .verify(&self.y1, &self.y2, &self.r1, &self.r2, &self.c, &self.s)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    fn login(cp: &ChaumPedersen, x: &BigUint, y1: &BigUint, y2: &BigUint) -> bool {
+        let prover = Prover::commit(cp, x);
+        let (r1, r2) = prover.commitment();
+
+        let verifier = Verifier::new(cp, y1, y2, r1, r2).challenge();
+        let prover = prover
+            .receive_challenge(verifier.challenge_value())
+            .respond();
+
+        verifier.receive_response(prover.response()).verify()
+    }
+
+    #[test]
+    fn test_session_flow() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let (y1, y2) = cp.compute_pair(&x);
+
+        assert!(login(&cp, &x, &y1, &y2));
+        assert!(!login(&cp, &(&x + 1u32), &y1, &y2));
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let cp = constants();
+        let x = BigUint::from(0x5ec2e7u32);
+        let prover = Prover::commit(&cp, &x);
+
+        let debug = format!("{:?}", prover);
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains(&x.to_string()));
+        assert!(!debug.contains(&prover.k.to_string()));
+    }
+}
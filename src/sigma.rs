@@ -0,0 +1,770 @@
+//! Generic three-move (sigma) protocols and combinators.
+//!
+//! A `SigmaProtocol` is a commit / challenge / respond / verify proof with a
+//! simulator. `And` proves two statements under one challenge, `Or` proves
+//! one of two by simulating the other with a split challenge, `AnyOf` does
+//! the same for one of n, and `FiatShamir` turns any protocol whose
+//! statement and commitment can be encoded into a non-interactive proof
+//! bound to a context string.
+
+use crate::{
+    transcript::{hash_to_scalar, HashFunction},
+    ChaumPedersen, Error, Result,
+};
+use num_bigint::BigUint;
+
+pub trait SigmaProtocol {
+    type Statement;
+    type Witness;
+    type Commitment;
+    type Response;
+    /// Prover randomness kept between `commit` and `respond`
+    type State;
+
+    /// Challenges are drawn from Z_q
+    fn challenge_modulus(&self) -> &BigUint;
+
+    /// Fails if `witness` cannot be used for `statement`
+    fn commit(
+        &self,
+        statement: &Self::Statement,
+        witness: &Self::Witness,
+    ) -> Result<(Self::State, Self::Commitment)>;
+
+    fn challenge(&self) -> BigUint {
+        ChaumPedersen::generate_random_below(self.challenge_modulus())
+    }
+
+    /// Fails if `witness` and `state` do not belong together
+    fn respond(
+        &self,
+        statement: &Self::Statement,
+        witness: &Self::Witness,
+        state: Self::State,
+        challenge: &BigUint,
+    ) -> Result<Self::Response>;
+
+    fn verify(
+        &self,
+        statement: &Self::Statement,
+        commitment: &Self::Commitment,
+        challenge: &BigUint,
+        response: &Self::Response,
+    ) -> bool;
+
+    /// Accepting (commitment, response) for `challenge` without the witness;
+    /// fails if `statement` is malformed for this protocol
+    fn simulate(
+        &self,
+        statement: &Self::Statement,
+        challenge: &BigUint,
+    ) -> Result<(Self::Commitment, Self::Response)>;
+}
+
+/// Byte encoding of statements, commitments and protocol parameters for
+/// Fiat-Shamir
+pub trait Encode {
+    fn encode(&self, out: &mut Vec<Vec<u8>>);
+}
+
+impl Encode for BigUint {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        out.push(self.to_bytes_be());
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        out.push((self.len() as u64).to_be_bytes().to_vec());
+        for item in self {
+            item.encode(out);
+        }
+    }
+}
+
+/// The group, by its fingerprint
+impl Encode for ChaumPedersen {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        out.push(self.fingerprint());
+    }
+}
+
+/// log_alpha(y1) == log_beta(y2): statement (y1, y2), witness x
+impl SigmaProtocol for ChaumPedersen {
+    type Statement = (BigUint, BigUint);
+    type Witness = BigUint;
+    type Commitment = (BigUint, BigUint);
+    type Response = BigUint;
+    type State = BigUint;
+
+    fn challenge_modulus(&self) -> &BigUint {
+        &self.q
+    }
+
+    fn commit(
+        &self,
+        _: &Self::Statement,
+        _: &Self::Witness,
+    ) -> Result<(BigUint, (BigUint, BigUint))> {
+        let k = ChaumPedersen::generate_random_below(&self.q);
+        let commitment = self.compute_pair(&k);
+        Ok((k, commitment))
+    }
+
+    fn respond(
+        &self,
+        _: &Self::Statement,
+        x: &BigUint,
+        k: BigUint,
+        c: &BigUint,
+    ) -> Result<BigUint> {
+        Ok(self.solve(&k, c, x))
+    }
+
+    fn verify(
+        &self,
+        (y1, y2): &Self::Statement,
+        (r1, r2): &Self::Commitment,
+        c: &BigUint,
+        s: &BigUint,
+    ) -> bool {
+        ChaumPedersen::verify(self, y1, y2, r1, r2, c, s)
+    }
+
+    fn simulate(
+        &self,
+        (y1, y2): &Self::Statement,
+        c: &BigUint,
+    ) -> Result<((BigUint, BigUint), BigUint)> {
+        let s = ChaumPedersen::generate_random_below(&self.q);
+        let r1 = (self.alpha.modpow(&s, &self.p) * y1.modpow(c, &self.p)) % &self.p;
+        let r2 = (self.beta.modpow(&s, &self.p) * y2.modpow(c, &self.p)) % &self.p;
+        Ok(((r1, r2), s))
+    }
+}
+
+/// Knowledge of log_g(y) (Schnorr identification): statement y, witness x
+#[derive(Debug, Clone)]
+pub struct DiscreteLog {
+    pub p: BigUint,
+    pub q: BigUint,
+    pub g: BigUint,
+}
+
+impl Encode for DiscreteLog {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        self.p.encode(out);
+        self.q.encode(out);
+        self.g.encode(out);
+    }
+}
+
+impl SigmaProtocol for DiscreteLog {
+    type Statement = BigUint;
+    type Witness = BigUint;
+    type Commitment = BigUint;
+    type Response = BigUint;
+    type State = BigUint;
+
+    fn challenge_modulus(&self) -> &BigUint {
+        &self.q
+    }
+
+    fn commit(&self, _: &BigUint, _: &BigUint) -> Result<(BigUint, BigUint)> {
+        let k = ChaumPedersen::generate_random_below(&self.q);
+        let r = self.g.modpow(&k, &self.p);
+        Ok((k, r))
+    }
+
+    /// s = k - c * x mod q
+    fn respond(&self, _: &BigUint, x: &BigUint, k: BigUint, c: &BigUint) -> Result<BigUint> {
+        let cx = (c * x) % &self.q;
+        Ok((k + &self.q - cx) % &self.q)
+    }
+
+    /// r == g^s * y^c
+    fn verify(&self, y: &BigUint, r: &BigUint, c: &BigUint, s: &BigUint) -> bool {
+        *r == (self.g.modpow(s, &self.p) * y.modpow(c, &self.p)) % &self.p
+    }
+
+    fn simulate(&self, y: &BigUint, c: &BigUint) -> Result<(BigUint, BigUint)> {
+        let s = ChaumPedersen::generate_random_below(&self.q);
+        let r = (self.g.modpow(&s, &self.p) * y.modpow(c, &self.p)) % &self.p;
+        Ok((r, s))
+    }
+}
+
+/// Both statements, answered with the same challenge
+#[derive(Debug, Clone)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: Encode, B: Encode> Encode for And<A, B> {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        out.push(b"and".to_vec());
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<A: SigmaProtocol, B: SigmaProtocol> SigmaProtocol for And<A, B> {
+    type Statement = (A::Statement, B::Statement);
+    type Witness = (A::Witness, B::Witness);
+    type Commitment = (A::Commitment, B::Commitment);
+    type Response = (A::Response, B::Response);
+    type State = (A::State, B::State);
+
+    fn challenge_modulus(&self) -> &BigUint {
+        debug_assert_eq!(self.0.challenge_modulus(), self.1.challenge_modulus());
+        self.0.challenge_modulus()
+    }
+
+    fn commit(
+        &self,
+        (sa, sb): &Self::Statement,
+        (wa, wb): &Self::Witness,
+    ) -> Result<(Self::State, Self::Commitment)> {
+        let (state_a, commitment_a) = self.0.commit(sa, wa)?;
+        let (state_b, commitment_b) = self.1.commit(sb, wb)?;
+        Ok(((state_a, state_b), (commitment_a, commitment_b)))
+    }
+
+    fn respond(
+        &self,
+        (sa, sb): &Self::Statement,
+        (wa, wb): &Self::Witness,
+        (state_a, state_b): Self::State,
+        c: &BigUint,
+    ) -> Result<Self::Response> {
+        Ok((
+            self.0.respond(sa, wa, state_a, c)?,
+            self.1.respond(sb, wb, state_b, c)?,
+        ))
+    }
+
+    fn verify(
+        &self,
+        (sa, sb): &Self::Statement,
+        (ca, cb): &Self::Commitment,
+        c: &BigUint,
+        (ra, rb): &Self::Response,
+    ) -> bool {
+        self.0.verify(sa, ca, c, ra) && self.1.verify(sb, cb, c, rb)
+    }
+
+    fn simulate(
+        &self,
+        (sa, sb): &Self::Statement,
+        c: &BigUint,
+    ) -> Result<(Self::Commitment, Self::Response)> {
+        let (commitment_a, response_a) = self.0.simulate(sa, c)?;
+        let (commitment_b, response_b) = self.1.simulate(sb, c)?;
+        Ok(((commitment_a, commitment_b), (response_a, response_b)))
+    }
+}
+
+/// Which side of an `Or` the prover knows a witness for
+#[derive(Debug, Clone)]
+pub enum OrWitness<L, R> {
+    Left(L),
+    Right(R),
+}
+
+/// The known side's state plus the simulated side's challenge and response
+pub enum OrState<A: SigmaProtocol, B: SigmaProtocol> {
+    Left(A::State, BigUint, B::Response),
+    Right(B::State, BigUint, A::Response),
+}
+
+/// (c_a, response_a, response_b) with c_b = c - c_a mod q
+pub type OrResponse<A, B> = (
+    BigUint,
+    <A as SigmaProtocol>::Response,
+    <B as SigmaProtocol>::Response,
+);
+
+/// One of two statements, without revealing which
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A: SigmaProtocol, B: SigmaProtocol> Or<A, B> {
+    fn split(&self, c: &BigUint, part: &BigUint) -> BigUint {
+        let q = self.0.challenge_modulus();
+        (c + q - part % q) % q
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for Or<A, B> {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        out.push(b"or".to_vec());
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<A: SigmaProtocol, B: SigmaProtocol> SigmaProtocol for Or<A, B> {
+    type Statement = (A::Statement, B::Statement);
+    type Witness = OrWitness<A::Witness, B::Witness>;
+    type Commitment = (A::Commitment, B::Commitment);
+    type Response = OrResponse<A, B>;
+    type State = OrState<A, B>;
+
+    fn challenge_modulus(&self) -> &BigUint {
+        debug_assert_eq!(self.0.challenge_modulus(), self.1.challenge_modulus());
+        self.0.challenge_modulus()
+    }
+
+    fn commit(
+        &self,
+        (sa, sb): &Self::Statement,
+        witness: &Self::Witness,
+    ) -> Result<(Self::State, Self::Commitment)> {
+        match witness {
+            OrWitness::Left(wa) => {
+                let c_b = self.1.challenge();
+                let (commitment_b, response_b) = self.1.simulate(sb, &c_b)?;
+                let (state_a, commitment_a) = self.0.commit(sa, wa)?;
+                Ok((
+                    OrState::Left(state_a, c_b, response_b),
+                    (commitment_a, commitment_b),
+                ))
+            }
+            OrWitness::Right(wb) => {
+                let c_a = self.0.challenge();
+                let (commitment_a, response_a) = self.0.simulate(sa, &c_a)?;
+                let (state_b, commitment_b) = self.1.commit(sb, wb)?;
+                Ok((
+                    OrState::Right(state_b, c_a, response_a),
+                    (commitment_a, commitment_b),
+                ))
+            }
+        }
+    }
+
+    fn respond(
+        &self,
+        (sa, sb): &Self::Statement,
+        witness: &Self::Witness,
+        state: Self::State,
+        c: &BigUint,
+    ) -> Result<Self::Response> {
+        match (witness, state) {
+            (OrWitness::Left(wa), OrState::Left(state_a, c_b, response_b)) => {
+                let c_a = self.split(c, &c_b);
+                let response_a = self.0.respond(sa, wa, state_a, &c_a)?;
+                Ok((c_a, response_a, response_b))
+            }
+            (OrWitness::Right(wb), OrState::Right(state_b, c_a, response_a)) => {
+                let c_b = self.split(c, &c_a);
+                let response_b = self.1.respond(sb, wb, state_b, &c_b)?;
+                Ok((c_a, response_a, response_b))
+            }
+            _ => Err(Error::FailedPrecondition(
+                "Or: witness side does not match the commitment".into(),
+            )),
+        }
+    }
+
+    fn verify(
+        &self,
+        (sa, sb): &Self::Statement,
+        (commitment_a, commitment_b): &Self::Commitment,
+        c: &BigUint,
+        (c_a, response_a, response_b): &Self::Response,
+    ) -> bool {
+        let c_b = self.split(c, c_a);
+
+        *c_a < *self.challenge_modulus()
+            && self.0.verify(sa, commitment_a, c_a, response_a)
+            && self.1.verify(sb, commitment_b, &c_b, response_b)
+    }
+
+    fn simulate(
+        &self,
+        (sa, sb): &Self::Statement,
+        c: &BigUint,
+    ) -> Result<(Self::Commitment, Self::Response)> {
+        let c_a = self.0.challenge();
+        let c_b = self.split(c, &c_a);
+        let (commitment_a, response_a) = self.0.simulate(sa, &c_a)?;
+        let (commitment_b, response_b) = self.1.simulate(sb, &c_b)?;
+        Ok(((commitment_a, commitment_b), (c_a, response_a, response_b)))
+    }
+}
+
+/// Prover state for `AnyOf`: the real branch's state, and the simulated
+/// (challenge, response) of every other branch
+pub struct AnyOfState<P: SigmaProtocol> {
+    real: P::State,
+    simulated: Vec<Option<(BigUint, P::Response)>>,
+}
+
+/// One of n statements, without revealing which: the n-ary `Or`, with
+/// challenges c_i summing to c mod q. The witness is (index, witness).
+#[derive(Debug, Clone)]
+pub struct AnyOf<P>(pub P);
+
+impl<P: Encode> Encode for AnyOf<P> {
+    fn encode(&self, out: &mut Vec<Vec<u8>>) {
+        out.push(b"any-of".to_vec());
+        self.0.encode(out);
+    }
+}
+
+impl<P: SigmaProtocol> SigmaProtocol for AnyOf<P> {
+    type Statement = Vec<P::Statement>;
+    type Witness = (usize, P::Witness);
+    type Commitment = Vec<P::Commitment>;
+    /// (c_i, response_i) per statement
+    type Response = Vec<(BigUint, P::Response)>;
+    type State = AnyOfState<P>;
+
+    fn challenge_modulus(&self) -> &BigUint {
+        self.0.challenge_modulus()
+    }
+
+    fn commit(
+        &self,
+        statements: &Self::Statement,
+        (index, witness): &Self::Witness,
+    ) -> Result<(Self::State, Self::Commitment)> {
+        if *index >= statements.len() {
+            return Err(Error::InvalidParameters(format!(
+                "AnyOf: witness index {} out of range for {} statements",
+                index,
+                statements.len()
+            )));
+        }
+
+        let mut real = None;
+        let mut simulated = Vec::with_capacity(statements.len());
+        let mut commitments = Vec::with_capacity(statements.len());
+
+        for (i, statement) in statements.iter().enumerate() {
+            if i == *index {
+                let (state, commitment) = self.0.commit(statement, witness)?;
+                real = Some(state);
+                simulated.push(None);
+                commitments.push(commitment);
+            } else {
+                let c = self.0.challenge();
+                let (commitment, response) = self.0.simulate(statement, &c)?;
+                simulated.push(Some((c, response)));
+                commitments.push(commitment);
+            }
+        }
+
+        let real = real.ok_or_else(|| {
+            Error::InvalidParameters("AnyOf: no statement for the witness".into())
+        })?;
+        Ok((AnyOfState { real, simulated }, commitments))
+    }
+
+    fn respond(
+        &self,
+        statements: &Self::Statement,
+        (index, witness): &Self::Witness,
+        state: Self::State,
+        c: &BigUint,
+    ) -> Result<Self::Response> {
+        let mismatch = || {
+            Error::FailedPrecondition("AnyOf: witness index does not match the commitment".into())
+        };
+        if state.simulated.len() != statements.len()
+            || !matches!(state.simulated.get(*index), Some(None))
+        {
+            return Err(mismatch());
+        }
+
+        let q = self.challenge_modulus();
+        let simulated_sum = state
+            .simulated
+            .iter()
+            .flatten()
+            .fold(BigUint::default(), |sum, (c_i, _)| (sum + c_i) % q);
+        let c_real = (c + q - simulated_sum) % q;
+
+        let response = self
+            .0
+            .respond(&statements[*index], witness, state.real, &c_real)?;
+
+        // the one unsimulated branch takes the real response
+        let mut real = Some((c_real, response));
+        state
+            .simulated
+            .into_iter()
+            .map(|simulated| simulated.or_else(|| real.take()))
+            .collect::<Option<_>>()
+            .ok_or_else(mismatch)
+    }
+
+    fn verify(
+        &self,
+        statements: &Self::Statement,
+        commitments: &Self::Commitment,
+        c: &BigUint,
+        responses: &Self::Response,
+    ) -> bool {
+        let q = self.challenge_modulus();
+
+        if statements.is_empty()
+            || commitments.len() != statements.len()
+            || responses.len() != statements.len()
+            || responses.iter().any(|(c_i, _)| c_i >= q)
+        {
+            return false;
+        }
+
+        let sum = responses
+            .iter()
+            .fold(BigUint::default(), |sum, (c_i, _)| (sum + c_i) % q);
+
+        sum == c % q
+            && statements.iter().zip(commitments).zip(responses).all(
+                |((statement, commitment), (c_i, response))| {
+                    self.0.verify(statement, commitment, c_i, response)
+                },
+            )
+    }
+
+    fn simulate(
+        &self,
+        statements: &Self::Statement,
+        c: &BigUint,
+    ) -> Result<(Self::Commitment, Self::Response)> {
+        let q = self.challenge_modulus();
+        let mut sum = BigUint::default();
+        let mut commitments = Vec::with_capacity(statements.len());
+        let mut responses = Vec::with_capacity(statements.len());
+
+        for (i, statement) in statements.iter().enumerate() {
+            let c_i = if i + 1 == statements.len() {
+                (c + q - &sum) % q
+            } else {
+                self.0.challenge()
+            };
+            sum = (sum + &c_i) % q;
+
+            let (commitment, response) = self.0.simulate(statement, &c_i)?;
+            commitments.push(commitment);
+            responses.push((c_i, response));
+        }
+
+        Ok((commitments, responses))
+    }
+}
+
+/// Non-interactive proof with
+/// c = H(protocol parameters, context, statement, commitment) mod q, so a
+/// proof made in one group does not verify in another
+#[derive(Debug, Clone)]
+pub struct FiatShamir<P>(pub P);
+
+impl<P> FiatShamir<P>
+where
+    P: SigmaProtocol + Encode,
+    P::Statement: Encode,
+    P::Commitment: Encode,
+{
+    fn challenge(
+        &self,
+        statement: &P::Statement,
+        commitment: &P::Commitment,
+        context: &[u8],
+    ) -> BigUint {
+        let mut items = Vec::new();
+        self.0.encode(&mut items);
+        items.push(context.to_vec());
+        statement.encode(&mut items);
+        commitment.encode(&mut items);
+
+        let items: Vec<&[u8]> = items.iter().map(Vec::as_slice).collect();
+        hash_to_scalar(
+            HashFunction::Sha256,
+            b"fiat-shamir",
+            &items,
+            self.0.challenge_modulus(),
+        )
+    }
+
+    pub fn prove(
+        &self,
+        statement: &P::Statement,
+        witness: &P::Witness,
+        context: &[u8],
+    ) -> Result<(P::Commitment, P::Response)> {
+        let (state, commitment) = self.0.commit(statement, witness)?;
+        let c = self.challenge(statement, &commitment, context);
+        let response = self.0.respond(statement, witness, state, &c)?;
+        Ok((commitment, response))
+    }
+
+    pub fn verify(
+        &self,
+        statement: &P::Statement,
+        context: &[u8],
+        (commitment, response): &(P::Commitment, P::Response),
+    ) -> bool {
+        let c = self.challenge(statement, commitment, context);
+        self.0.verify(statement, commitment, &c, response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    fn run<P: SigmaProtocol>(protocol: &P, statement: &P::Statement, witness: &P::Witness) -> bool {
+        let (state, commitment) = protocol.commit(statement, witness).unwrap();
+        let c = protocol.challenge();
+        let response = protocol.respond(statement, witness, state, &c).unwrap();
+        protocol.verify(statement, &commitment, &c, &response)
+    }
+
+    #[test]
+    fn test_interactive_and_simulated() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let statement = cp.compute_pair(&x);
+
+        assert!(run(&cp, &statement, &x));
+        assert!(!run(&cp, &statement, &(&x + 1u32)));
+
+        let c = cp.challenge();
+        let (commitment, response) = cp.simulate(&statement, &c).unwrap();
+        assert!(SigmaProtocol::verify(
+            &cp,
+            &statement,
+            &commitment,
+            &c,
+            &response
+        ));
+    }
+
+    #[test]
+    fn test_and_or() {
+        let cp = constants();
+        let dl = DiscreteLog {
+            p: cp.p.clone(),
+            q: cp.q.clone(),
+            g: cp.alpha.clone(),
+        };
+
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let w = ChaumPedersen::generate_random_below(&cp.q);
+        let pair = cp.compute_pair(&x);
+        let y = cp.alpha.modpow(&w, &cp.p);
+
+        let and = And(cp.clone(), dl.clone());
+        assert!(run(
+            &and,
+            &(pair.clone(), y.clone()),
+            &(x.clone(), w.clone())
+        ));
+        assert!(!run(
+            &and,
+            &(pair.clone(), y.clone()),
+            &(x.clone(), &w + 1u32)
+        ));
+
+        // either witness satisfies the disjunction
+        let or = Or(cp.clone(), dl);
+        let statement = (pair, y);
+        assert!(run(&or, &statement, &OrWitness::Left(x.clone())));
+        assert!(run(&or, &statement, &OrWitness::Right(w.clone())));
+        assert!(!run(&or, &statement, &OrWitness::Left(&x + 1u32)));
+
+        // answering with the other side's witness is an error, not a panic
+        let (state, _) = or.commit(&statement, &OrWitness::Left(x)).unwrap();
+        assert!(matches!(
+            or.respond(&statement, &OrWitness::Right(w), state, &or.challenge()),
+            Err(Error::FailedPrecondition(_))
+        ));
+    }
+
+    #[test]
+    fn test_any_of() {
+        let cp = constants();
+        let secrets: Vec<BigUint> = (0..4)
+            .map(|_| ChaumPedersen::generate_random_below(&cp.q))
+            .collect();
+        let statement: Vec<_> = secrets.iter().map(|x| cp.compute_pair(x)).collect();
+
+        let any = AnyOf(cp.clone());
+        for (i, x) in secrets.iter().enumerate() {
+            assert!(run(&any, &statement, &(i, x.clone())));
+        }
+        assert!(!run(&any, &statement, &(0, secrets[1].clone())));
+        assert!(matches!(
+            any.commit(&statement, &(4, secrets[0].clone())),
+            Err(Error::InvalidParameters(_))
+        ));
+
+        // answering for another index, or out of range, is an error, not a panic
+        for index in [1, 4] {
+            let (state, _) = any.commit(&statement, &(0, secrets[0].clone())).unwrap();
+            assert!(matches!(
+                any.respond(
+                    &statement,
+                    &(index, secrets[0].clone()),
+                    state,
+                    &any.challenge()
+                ),
+                Err(Error::FailedPrecondition(_))
+            ));
+        }
+
+        let proof_system = FiatShamir(any);
+        let proof = proof_system
+            .prove(&statement, &(2, secrets[2].clone()), b"auth-id")
+            .unwrap();
+        assert!(proof_system.verify(&statement, b"auth-id", &proof));
+        assert!(!proof_system.verify(&statement[1..].to_vec(), b"auth-id", &proof));
+    }
+
+    #[test]
+    fn test_fiat_shamir_composition() {
+        let cp = constants();
+        let dl = DiscreteLog {
+            p: cp.p.clone(),
+            q: cp.q.clone(),
+            g: cp.alpha.clone(),
+        };
+
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let w = ChaumPedersen::generate_random_below(&cp.q);
+        let statement = (cp.compute_pair(&x), cp.alpha.modpow(&w, &cp.p));
+
+        // the designated-verifier statement, composed mechanically
+        let proof_system = FiatShamir(Or(cp.clone(), dl));
+        let proof = proof_system
+            .prove(&statement, &OrWitness::Left(x), b"session-1")
+            .unwrap();
+
+        assert!(proof_system.verify(&statement, b"session-1", &proof));
+        assert!(!proof_system.verify(&statement, b"session-2", &proof));
+
+        // the challenge depends on the group, not only on what is sent
+        let (commitment, _) = &proof;
+        let swapped = ChaumPedersen {
+            alpha: cp.beta.clone(),
+            beta: cp.alpha.clone(),
+            ..cp.clone()
+        };
+        assert_ne!(
+            FiatShamir(cp).challenge(&statement.0, &commitment.0, b"session-1"),
+            FiatShamir(swapped).challenge(&statement.0, &commitment.0, b"session-1")
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Threshold ElGamal over the Chaum-Pedersen group.
+//!
+//! The decryption key x is Shamir-shared among n holders so that any t of
+//! them can decrypt. Holder i publishes h_i = alpha^x_i at setup; for a
+//! ciphertext (c1, c2) it returns d_i = c1^x_i together with a Chaum-Pedersen
+//! proof that log_alpha(h_i) == log_c1(d_i), so the combiner can detect and
+//! drop cheating shares before interpolating.
+
+use crate::poly::{check_threshold, lagrange_coefficient, Polynomial};
+use crate::{ChaumPedersen, Error, Proof, Result};
+use num_bigint::BigUint;
+use std::collections::HashMap;
+
+/// Public part of a shared key: y = alpha^x and h_i = alpha^x_i per holder
+#[derive(Debug, Clone)]
+pub struct ThresholdPublicKey {
+    pub threshold: usize,
+    pub y: BigUint,
+    pub verification_keys: HashMap<u32, BigUint>,
+}
+
+/// Secret share x_i = f(i) of holder i
+#[derive(Debug, Clone)]
+pub struct KeyShare {
+    pub id: u32,
+    pub x: BigUint,
+}
+
+/// (c1, c2) = (alpha^r, m * y^r)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub c1: BigUint,
+    pub c2: BigUint,
+}
+
+/// d_i = c1^x_i with a proof that log_alpha(h_i) == log_c1(d_i)
+#[derive(Debug, Clone)]
+pub struct PartialDecryption {
+    pub id: u32,
+    pub d: BigUint,
+    pub proof: Proof,
+}
+
+/// A c1 of small order would make each d_i = c1^x_i leak x_i mod the small
+/// factors of p - 1
+fn check_ciphertext(cp: &ChaumPedersen, ct: &Ciphertext) -> Result<()> {
+    if !cp.is_element(&ct.c1) {
+        return Err(Error::Decode("c1 is not in the order-q subgroup".into()));
+    }
+    Ok(())
+}
+
+/// Splits a fresh key into n shares, any `threshold` of which can decrypt
+pub fn deal(
+    cp: &ChaumPedersen,
+    threshold: usize,
+    n: u32,
+) -> Result<(ThresholdPublicKey, Vec<KeyShare>)> {
+    check_threshold(threshold, n)?;
+
+    let x = ChaumPedersen::generate_random_below(&cp.q);
+    let f = Polynomial::random(&x, threshold - 1, &cp.q);
+
+    let shares: Vec<KeyShare> = (1..=n)
+        .map(|id| KeyShare {
+            id,
+            x: f.evaluate(&BigUint::from(id)),
+        })
+        .collect();
+
+    let verification_keys = shares
+        .iter()
+        .map(|share| (share.id, cp.alpha.modpow(&share.x, &cp.p)))
+        .collect();
+
+    let public_key = ThresholdPublicKey {
+        threshold,
+        y: cp.alpha.modpow(&x, &cp.p),
+        verification_keys,
+    };
+
+    Ok((public_key, shares))
+}
+
+/// m must be an element of the order-q subgroup
+pub fn encrypt(cp: &ChaumPedersen, y: &BigUint, m: &BigUint) -> Ciphertext {
+    let r = ChaumPedersen::generate_random_below(&cp.q);
+
+    Ciphertext {
+        c1: cp.alpha.modpow(&r, &cp.p),
+        c2: (m * y.modpow(&r, &cp.p)) % &cp.p,
+    }
+}
+
+/// Chaum-Pedersen instance with beta = c1, so compute_pair(x_i) = (h_i, d_i)
+fn share_instance(cp: &ChaumPedersen, ct: &Ciphertext) -> ChaumPedersen {
+    ChaumPedersen {
+        p: cp.p.clone(),
+        q: cp.q.clone(),
+        alpha: cp.alpha.clone(),
+        beta: ct.c1.clone(),
+    }
+}
+
+impl KeyShare {
+    /// Fails unless c1 is in the order-q subgroup
+    pub fn decrypt_share(&self, cp: &ChaumPedersen, ct: &Ciphertext) -> Result<PartialDecryption> {
+        check_ciphertext(cp, ct)?;
+        let instance = share_instance(cp, ct);
+
+        Ok(PartialDecryption {
+            id: self.id,
+            d: ct.c1.modpow(&self.x, &cp.p),
+            proof: instance.prove(&self.x),
+        })
+    }
+}
+
+/// Checks the share proof against the holder's registered h_i; fails
+/// unless c1 is in the order-q subgroup
+pub fn verify_share(
+    cp: &ChaumPedersen,
+    public_key: &ThresholdPublicKey,
+    ct: &Ciphertext,
+    share: &PartialDecryption,
+) -> Result<bool> {
+    check_ciphertext(cp, ct)?;
+    let h = match public_key.verification_keys.get(&share.id) {
+        Some(h) => h,
+        None => return Ok(false),
+    };
+
+    Ok(cp.is_element(&share.d) && share_instance(cp, ct).verify_proof(h, &share.d, &share.proof))
+}
+
+/// m = c2 / prod d_i^lambda_i over the first t valid shares; fails unless
+/// c1 is in the order-q subgroup and at least t shares verify
+pub fn combine(
+    cp: &ChaumPedersen,
+    public_key: &ThresholdPublicKey,
+    ct: &Ciphertext,
+    shares: &[PartialDecryption],
+) -> Result<BigUint> {
+    check_ciphertext(cp, ct)?;
+
+    let mut valid: Vec<&PartialDecryption> = Vec::new();
+    let mut rejected = Vec::new();
+
+    for share in shares {
+        if valid.iter().any(|v| v.id == share.id) {
+            continue;
+        }
+        if verify_share(cp, public_key, ct, share) == Ok(true) {
+            valid.push(share);
+        } else {
+            rejected.push(share.id);
+        }
+    }
+
+    if valid.len() < public_key.threshold {
+        return Err(Error::Verification(format!(
+            "{} valid partial decryptions, {} needed; rejected ids {:?}",
+            valid.len(),
+            public_key.threshold,
+            rejected
+        )));
+    }
+
+    let valid = &valid[..public_key.threshold];
+    let ids: Vec<u32> = valid.iter().map(|share| share.id).collect();
+
+    let mask = valid.iter().fold(BigUint::from(1u32), |acc, share| {
+        let lambda = lagrange_coefficient(&ids, share.id, &cp.q);
+        (acc * share.d.modpow(&lambda, &cp.p)) % &cp.p
+    });
+
+    // p is prime: mask^-1 = mask^(p-2) mod p
+    let inverse = mask.modpow(&(&cp.p - BigUint::from(2u32)), &cp.p);
+    Ok((&ct.c2 * inverse) % &cp.p)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    #[test]
+    fn test_any_threshold_subset_decrypts() {
+        let cp = constants();
+        let (public_key, shares) = deal(&cp, 3, 5).unwrap();
+
+        let m = cp.alpha.modpow(&BigUint::from(424242u32), &cp.p);
+        let ct = encrypt(&cp, &public_key.y, &m);
+
+        for subset in [[0, 1, 2], [1, 3, 4], [4, 0, 2]] {
+            let partials: Vec<PartialDecryption> = subset
+                .iter()
+                .map(|&i| shares[i].decrypt_share(&cp, &ct).unwrap())
+                .collect();
+
+            assert_eq!(combine(&cp, &public_key, &ct, &partials), Ok(m.clone()));
+        }
+    }
+
+    #[test]
+    fn test_cheating_share_is_rejected() {
+        let cp = constants();
+        let (public_key, shares) = deal(&cp, 2, 3).unwrap();
+
+        let m = cp.alpha.modpow(&BigUint::from(7u32), &cp.p);
+        let ct = encrypt(&cp, &public_key.y, &m);
+
+        // holder 2 answers with another share's key
+        let mut cheat = shares[1].decrypt_share(&cp, &ct).unwrap();
+        let forged = KeyShare {
+            id: 2,
+            x: shares[2].x.clone(),
+        };
+        cheat.d = forged.decrypt_share(&cp, &ct).unwrap().d;
+        assert_eq!(verify_share(&cp, &public_key, &ct, &cheat), Ok(false));
+
+        let honest = shares[0].decrypt_share(&cp, &ct).unwrap();
+        assert_eq!(
+            combine(&cp, &public_key, &ct, &[honest.clone(), cheat.clone()]),
+            Err(Error::Verification(
+                "1 valid partial decryptions, 2 needed; rejected ids [2]".into()
+            ))
+        );
+
+        let honest_third = shares[2].decrypt_share(&cp, &ct).unwrap();
+        assert_eq!(
+            combine(&cp, &public_key, &ct, &[cheat, honest, honest_third]),
+            Ok(m)
+        );
+    }
+
+    #[test]
+    fn test_small_order_c1_is_rejected() {
+        let cp = constants();
+        let (public_key, shares) = deal(&cp, 2, 3).unwrap();
+
+        let m = cp.alpha.modpow(&BigUint::from(7u32), &cp.p);
+        let mut ct = encrypt(&cp, &public_key.y, &m);
+        let honest = shares[0].decrypt_share(&cp, &ct).unwrap();
+
+        // p - 1 has order 2, so d_i would reveal x_i mod 2
+        ct.c1 = &cp.p - 1u32;
+        assert!(matches!(
+            shares[0].decrypt_share(&cp, &ct),
+            Err(Error::Decode(_))
+        ));
+        assert!(verify_share(&cp, &public_key, &ct, &honest).is_err());
+        assert!(matches!(
+            combine(&cp, &public_key, &ct, &[honest]),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_bad_threshold_is_rejected() {
+        let cp = constants();
+        assert!(matches!(deal(&cp, 0, 3), Err(Error::InvalidParameters(_))));
+        assert!(matches!(deal(&cp, 4, 3), Err(Error::InvalidParameters(_))));
+    }
+}
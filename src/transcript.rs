@@ -0,0 +1,221 @@
+//! Hashing into Z_q and into the order-q subgroup, and Fiat-Shamir transcripts.
+//!
+//! Every input is length-prefixed and every output is bound to a domain
+//! separation tag (DST), so the same bytes hashed for two purposes give
+//! unrelated results. Outputs are expanded 128 bits past the modulus before
+//! reduction, which keeps them statistically close to uniform.
+
+use alloc::{vec, vec::Vec};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use sha3::Sha3_256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashFunction {
+    #[default]
+    Sha256,
+    Sha3_256,
+    Blake3,
+}
+
+impl HashFunction {
+    /// H(part || part ...), 32 bytes for every choice
+    fn digest(&self, parts: &[&[u8]]) -> [u8; 32] {
+        match self {
+            HashFunction::Sha256 => {
+                let mut hasher = Sha256::new();
+                parts.iter().for_each(|part| hasher.update(part));
+                hasher.finalize().into()
+            }
+            HashFunction::Sha3_256 => {
+                let mut hasher = Sha3_256::new();
+                parts.iter().for_each(|part| hasher.update(part));
+                hasher.finalize().into()
+            }
+            HashFunction::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                parts.iter().for_each(|part| {
+                    hasher.update(part);
+                });
+                hasher.finalize().into()
+            }
+        }
+    }
+}
+
+fn length_prefixed(out: &mut Vec<u8>, item: &[u8]) {
+    out.extend_from_slice(&(item.len() as u64).to_be_bytes());
+    out.extend_from_slice(item);
+}
+
+/// `len` bytes of H(dst, counter, items) for counter = 0, 1, ...
+pub fn expand(hash: HashFunction, dst: &[u8], items: &[&[u8]], len: usize) -> Vec<u8> {
+    let mut input = Vec::new();
+    length_prefixed(&mut input, dst);
+    for item in items {
+        length_prefixed(&mut input, item);
+    }
+
+    let mut bytes = Vec::with_capacity(len + 32);
+    for counter in 0u64.. {
+        if bytes.len() >= len {
+            break;
+        }
+        bytes.extend_from_slice(&hash.digest(&[&counter.to_be_bytes(), &input]));
+    }
+    bytes.truncate(len);
+    bytes
+}
+
+/// Bytes needed for a near-uniform reduction mod `modulus`
+pub(crate) fn wide_len(modulus: &BigUint) -> usize {
+    (modulus.bits() as usize).div_ceil(8) + 16
+}
+
+/// Uniform-looking element of Z_q
+pub fn hash_to_scalar(hash: HashFunction, dst: &[u8], items: &[&[u8]], q: &BigUint) -> BigUint {
+    BigUint::from_bytes_be(&expand(hash, dst, items, wide_len(q))) % q
+}
+
+/// h = H(items)^((p-1)/q) mod p, an element of the order-q subgroup other
+/// than 1, whose discrete log to any fixed generator is unknown
+pub fn hash_to_subgroup(
+    hash: HashFunction,
+    dst: &[u8],
+    items: &[&[u8]],
+    p: &BigUint,
+    q: &BigUint,
+) -> BigUint {
+    let cofactor = (p - 1u32) / q;
+
+    let mut attempt = 0u64;
+    loop {
+        let counter = attempt.to_be_bytes();
+        let mut tagged = vec![counter.as_slice()];
+        tagged.extend_from_slice(items);
+
+        let h = BigUint::from_bytes_be(&expand(hash, dst, &tagged, wide_len(p))) % p;
+        let g = h.modpow(&cofactor, p);
+        if g > BigUint::from(1u32) {
+            return g;
+        }
+        attempt += 1;
+    }
+}
+
+/// Running Fiat-Shamir transcript: labelled messages are appended in order,
+/// and each challenge depends on everything before it, earlier challenges
+/// included.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    hash: HashFunction,
+    state: Vec<u8>,
+}
+
+impl Transcript {
+    pub fn new(hash: HashFunction, domain: &[u8]) -> Self {
+        let mut transcript = Transcript {
+            hash,
+            state: Vec::new(),
+        };
+        transcript.append(b"domain", domain);
+        transcript
+    }
+
+    pub fn append(&mut self, label: &[u8], message: &[u8]) {
+        length_prefixed(&mut self.state, label);
+        length_prefixed(&mut self.state, message);
+    }
+
+    pub fn append_biguint(&mut self, label: &[u8], value: &BigUint) {
+        self.append(label, &value.to_bytes_be());
+    }
+
+    pub fn challenge_bytes(&mut self, label: &[u8], len: usize) -> Vec<u8> {
+        let bytes = expand(
+            self.hash,
+            b"transcript-challenge",
+            &[&self.state, label],
+            len,
+        );
+        self.append(label, &bytes);
+        bytes
+    }
+
+    pub fn challenge_scalar(&mut self, label: &[u8], q: &BigUint) -> BigUint {
+        BigUint::from_bytes_be(&self.challenge_bytes(label, wide_len(q))) % q
+    }
+
+    pub fn challenge_element(&mut self, label: &[u8], p: &BigUint, q: &BigUint) -> BigUint {
+        let seed = self.challenge_bytes(label, 32);
+        hash_to_subgroup(self.hash, b"transcript-element", &[&seed], p, q)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ChaumPedersen;
+
+    const HASHES: [HashFunction; 3] = [
+        HashFunction::Sha256,
+        HashFunction::Sha3_256,
+        HashFunction::Blake3,
+    ];
+
+    #[test]
+    fn test_domain_separation() {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        let cp = ChaumPedersen {
+            alpha,
+            beta,
+            p: p.clone(),
+            q: q.clone(),
+        };
+
+        for hash in HASHES {
+            let a = hash_to_scalar(hash, b"dst-a", &[b"x"], &q);
+            assert!(a < q);
+            assert_eq!(a, hash_to_scalar(hash, b"dst-a", &[b"x"], &q));
+            assert_ne!(a, hash_to_scalar(hash, b"dst-b", &[b"x"], &q));
+            // length prefixes keep item boundaries apart
+            assert_ne!(
+                hash_to_scalar(hash, b"dst", &[b"ab", b"c"], &q),
+                hash_to_scalar(hash, b"dst", &[b"a", b"bc"], &q)
+            );
+
+            let h = hash_to_subgroup(hash, b"dst-a", &[b"x"], &p, &q);
+            assert!(cp.is_element(&h));
+            assert_ne!(h, hash_to_subgroup(hash, b"dst-b", &[b"x"], &p, &q));
+        }
+
+        assert_ne!(
+            hash_to_scalar(HashFunction::Sha256, b"dst", &[b"x"], &q),
+            hash_to_scalar(HashFunction::Blake3, b"dst", &[b"x"], &q)
+        );
+    }
+
+    #[test]
+    fn test_transcript() {
+        let (_, _, p, q) = ChaumPedersen::get_constants();
+
+        for hash in HASHES {
+            let mut a = Transcript::new(hash, b"test");
+            let mut b = Transcript::new(hash, b"test");
+            a.append(b"y1", b"123");
+            b.append(b"y1", b"123");
+
+            let c = a.challenge_scalar(b"c", &q);
+            assert_eq!(c, b.challenge_scalar(b"c", &q));
+            // a second challenge depends on the first
+            assert_ne!(c, a.challenge_scalar(b"c", &q));
+
+            let mut other = Transcript::new(hash, b"test");
+            other.append(b"y2", b"123");
+            assert_ne!(c, other.challenge_scalar(b"c", &q));
+
+            let h = b.challenge_element(b"h", &p, &q);
+            assert_eq!(h.modpow(&q, &p), BigUint::from(1u32));
+        }
+    }
+}
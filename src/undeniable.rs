@@ -0,0 +1,451 @@
+//! Chaum-van Antwerpen undeniable signatures.
+//!
+//! The signature on a message is z = m^x, where m is the message hashed into
+//! the group and y = alpha^x is the signer's public key. Nobody can check z
+//! alone: the signer confirms it with an interactive Chaum-Pedersen proof
+//! that log_alpha(y) == log_m(z), or proves that it is not their signature
+//! with the disavowal protocol.
+//!
+//! The confirmation verifier commits to its challenge before seeing the
+//! prover's commitment, which makes the protocol zero-knowledge against any
+//! verifier: a verifier can produce transcripts indistinguishable from real
+//! ones on its own, so a transcript convinces no third party.
+
+use crate::{
+    transcript::{expand, HashFunction},
+    ChaumPedersen, Error, Result,
+};
+use num_bigint::BigUint;
+use rand::Rng;
+
+/// Candidates per disavowal round; a cheating signer survives a round with
+/// probability 1/DISAVOWAL_RANGE
+pub const DISAVOWAL_RANGE: u32 = 16;
+
+/// m = H(message) in the order-q subgroup
+pub fn message_element(cp: &ChaumPedersen, message: &[u8]) -> BigUint {
+    cp.hash_to_group(&[b"undeniable", message])
+}
+
+/// z = m^x
+pub fn sign(cp: &ChaumPedersen, x: &BigUint, message: &[u8]) -> BigUint {
+    message_element(cp, message).modpow(x, &cp.p)
+}
+
+/// ChaumPedersen with beta = m, so compute_pair(x) = (y, z)
+fn confirmation_instance(cp: &ChaumPedersen, message: &[u8]) -> ChaumPedersen {
+    ChaumPedersen {
+        p: cp.p.clone(),
+        q: cp.q.clone(),
+        alpha: cp.alpha.clone(),
+        beta: message_element(cp, message),
+    }
+}
+
+fn commitment(value: &BigUint, nonce: &[u8; 32]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&expand(
+        HashFunction::Sha256,
+        b"commitment",
+        &[&value.to_bytes_be(), nonce],
+        32,
+    ));
+    out
+}
+
+/// Everything a confirmation verifier sees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmationTranscript {
+    pub challenge_commitment: [u8; 32],
+    pub r1: BigUint,
+    pub r2: BigUint,
+    pub c: BigUint,
+    pub nonce: [u8; 32],
+    pub s: BigUint,
+}
+
+/// Verifier side: commit to c, receive (r1, r2), open c, check s
+pub struct ConfirmationVerifier {
+    c: BigUint,
+    nonce: [u8; 32],
+}
+
+impl ConfirmationVerifier {
+    pub fn new(cp: &ChaumPedersen) -> (Self, [u8; 32]) {
+        let c = ChaumPedersen::generate_random_below(&cp.q);
+        let nonce = rand::thread_rng().gen::<[u8; 32]>();
+        let challenge_commitment = commitment(&c, &nonce);
+
+        (ConfirmationVerifier { c, nonce }, challenge_commitment)
+    }
+
+    /// Opening of the challenge, sent once (r1, r2) is received
+    pub fn open(&self) -> (BigUint, [u8; 32]) {
+        (self.c.clone(), self.nonce)
+    }
+
+    /// Rejects a z outside the order-q subgroup: with an order-2 component
+    /// the proof would still pass whenever c is even
+    pub fn verify(
+        self,
+        cp: &ChaumPedersen,
+        y: &BigUint,
+        message: &[u8],
+        z: &BigUint,
+        (r1, r2): &(BigUint, BigUint),
+        s: &BigUint,
+    ) -> bool {
+        cp.is_element(y)
+            && cp.is_element(z)
+            && confirmation_instance(cp, message).verify(y, z, r1, r2, &self.c, s)
+    }
+}
+
+/// Signer side of the confirmation protocol
+pub struct ConfirmationProver {
+    k: BigUint,
+    challenge_commitment: [u8; 32],
+}
+
+impl ConfirmationProver {
+    /// (r1, r2) = (alpha^k, m^k) after receiving the challenge commitment
+    pub fn new(
+        cp: &ChaumPedersen,
+        message: &[u8],
+        challenge_commitment: [u8; 32],
+    ) -> (Self, (BigUint, BigUint)) {
+        let k = ChaumPedersen::generate_random_below(&cp.q);
+        let pair = confirmation_instance(cp, message).compute_pair(&k);
+
+        (
+            ConfirmationProver {
+                k,
+                challenge_commitment,
+            },
+            pair,
+        )
+    }
+
+    /// s = k - c * x mod q, or an error if the opening does not match the commitment
+    pub fn respond(
+        self,
+        cp: &ChaumPedersen,
+        x: &BigUint,
+        c: &BigUint,
+        nonce: &[u8; 32],
+    ) -> Result<BigUint> {
+        if commitment(c, nonce) != self.challenge_commitment {
+            return Err(Error::Verification(
+                "challenge does not open the verifier's commitment".into(),
+            ));
+        }
+        Ok(cp.solve(&self.k, c, x))
+    }
+}
+
+/// Transcript produced by the verifier alone, without the signer's key
+pub fn simulate_confirmation(
+    cp: &ChaumPedersen,
+    y: &BigUint,
+    message: &[u8],
+    z: &BigUint,
+) -> ConfirmationTranscript {
+    let c = ChaumPedersen::generate_random_below(&cp.q);
+    let s = ChaumPedersen::generate_random_below(&cp.q);
+    let nonce = rand::thread_rng().gen::<[u8; 32]>();
+    let m = message_element(cp, message);
+
+    // r1 = alpha^s * y^c, r2 = m^s * z^c
+    let r1 = (cp.alpha.modpow(&s, &cp.p) * y.modpow(&c, &cp.p)) % &cp.p;
+    let r2 = (m.modpow(&s, &cp.p) * z.modpow(&c, &cp.p)) % &cp.p;
+
+    ConfirmationTranscript {
+        challenge_commitment: commitment(&c, &nonce),
+        r1,
+        r2,
+        c,
+        nonce,
+        s,
+    }
+}
+
+/// The checks a third party could run on a transcript shown to them
+pub fn transcript_is_consistent(
+    cp: &ChaumPedersen,
+    y: &BigUint,
+    message: &[u8],
+    z: &BigUint,
+    transcript: &ConfirmationTranscript,
+) -> bool {
+    commitment(&transcript.c, &transcript.nonce) == transcript.challenge_commitment
+        && cp.is_element(y)
+        && cp.is_element(z)
+        && confirmation_instance(cp, message).verify(
+            y,
+            z,
+            &transcript.r1,
+            &transcript.r2,
+            &transcript.c,
+            &transcript.s,
+        )
+}
+
+/// (c, d) = (m^e * alpha^f, z^e * y^f) for a secret e < DISAVOWAL_RANGE
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisavowalChallenge {
+    pub c: BigUint,
+    pub d: BigUint,
+}
+
+/// Verifier side of one disavowal round
+pub struct DisavowalVerifier {
+    e: u32,
+    f: BigUint,
+}
+
+impl DisavowalVerifier {
+    pub fn new(
+        cp: &ChaumPedersen,
+        y: &BigUint,
+        message: &[u8],
+        z: &BigUint,
+    ) -> (Self, DisavowalChallenge) {
+        let e = rand::thread_rng().gen_range(0..DISAVOWAL_RANGE);
+        let f = ChaumPedersen::generate_random_below(&cp.q);
+        let challenge = disavowal_challenge(cp, y, message, z, e, &f);
+
+        (DisavowalVerifier { e, f }, challenge)
+    }
+
+    /// (e, f), revealed once the prover has committed to its guess
+    pub fn reveal(&self) -> (u32, BigUint) {
+        (self.e, self.f.clone())
+    }
+
+    /// The round succeeds iff the opened guess equals e
+    pub fn accept(self, guess_commitment: &[u8; 32], guess: u32, nonce: &[u8; 32]) -> bool {
+        commitment(&BigUint::from(guess), nonce) == *guess_commitment && guess == self.e
+    }
+}
+
+fn disavowal_challenge(
+    cp: &ChaumPedersen,
+    y: &BigUint,
+    message: &[u8],
+    z: &BigUint,
+    e: u32,
+    f: &BigUint,
+) -> DisavowalChallenge {
+    let m = message_element(cp, message);
+    let e = BigUint::from(e);
+
+    DisavowalChallenge {
+        c: (m.modpow(&e, &cp.p) * cp.alpha.modpow(f, &cp.p)) % &cp.p,
+        d: (z.modpow(&e, &cp.p) * y.modpow(f, &cp.p)) % &cp.p,
+    }
+}
+
+/// Signer side of one disavowal round
+pub struct DisavowalProver {
+    guess: u32,
+    nonce: [u8; 32],
+    challenge: DisavowalChallenge,
+}
+
+impl DisavowalProver {
+    /// c^x / d = (m^x / z)^e, so e can be found by search unless z = m^x.
+    /// Fails with `Error::Verification` when z is the signer's genuine signature.
+    pub fn new(
+        cp: &ChaumPedersen,
+        x: &BigUint,
+        message: &[u8],
+        z: &BigUint,
+        challenge: DisavowalChallenge,
+    ) -> Result<(Self, [u8; 32])> {
+        let m_x = message_element(cp, message).modpow(x, &cp.p);
+        let inverse = |v: &BigUint| v.modpow(&(&cp.p - 2u32), &cp.p);
+
+        let w = (m_x * inverse(z)) % &cp.p;
+        if w == BigUint::from(1u32) {
+            return Err(Error::Verification(
+                "z is the signer's genuine signature".into(),
+            ));
+        }
+        let t = (challenge.c.modpow(x, &cp.p) * inverse(&challenge.d)) % &cp.p;
+
+        let guess = (0..DISAVOWAL_RANGE)
+            .find(|i| w.modpow(&BigUint::from(*i), &cp.p) == t)
+            .ok_or_else(|| Error::Verification("challenge is outside the range".into()))?;
+        let nonce = rand::thread_rng().gen::<[u8; 32]>();
+        let guess_commitment = commitment(&BigUint::from(guess), &nonce);
+
+        Ok((
+            DisavowalProver {
+                guess,
+                nonce,
+                challenge,
+            },
+            guess_commitment,
+        ))
+    }
+
+    /// Opens the guess once the verifier's (e, f) reproduces its challenge
+    pub fn open(
+        self,
+        cp: &ChaumPedersen,
+        y: &BigUint,
+        message: &[u8],
+        z: &BigUint,
+        e: u32,
+        f: &BigUint,
+    ) -> Result<(u32, [u8; 32])> {
+        if e >= DISAVOWAL_RANGE || disavowal_challenge(cp, y, message, z, e, f) != self.challenge {
+            return Err(Error::Verification(
+                "(e, f) does not reproduce the challenge".into(),
+            ));
+        }
+        Ok((self.guess, self.nonce))
+    }
+}
+
+/// Runs `rounds` disavowal rounds in-process; true iff the signer proved z is
+/// not their signature
+pub fn disavow(
+    cp: &ChaumPedersen,
+    x: &BigUint,
+    y: &BigUint,
+    message: &[u8],
+    z: &BigUint,
+    rounds: usize,
+) -> bool {
+    (0..rounds).all(|_| {
+        let (verifier, challenge) = DisavowalVerifier::new(cp, y, message, z);
+        let (prover, guess_commitment) = match DisavowalProver::new(cp, x, message, z, challenge) {
+            Ok(prover) => prover,
+            Err(_) => return false,
+        };
+        let (e, f) = verifier.reveal();
+        match prover.open(cp, y, message, z, e, &f) {
+            Ok((guess, nonce)) => verifier.accept(&guess_commitment, guess, &nonce),
+            Err(_) => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    fn confirm(cp: &ChaumPedersen, x: &BigUint, y: &BigUint, message: &[u8], z: &BigUint) -> bool {
+        let (verifier, challenge_commitment) = ConfirmationVerifier::new(cp);
+        let (prover, r) = ConfirmationProver::new(cp, message, challenge_commitment);
+        let (c, nonce) = verifier.open();
+        let s = prover.respond(cp, x, &c, &nonce).unwrap();
+        verifier.verify(cp, y, message, z, &r, &s)
+    }
+
+    #[test]
+    fn test_confirm_and_disavow() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let y = cp.alpha.modpow(&x, &cp.p);
+
+        let z = sign(&cp, &x, b"contract");
+        assert!(confirm(&cp, &x, &y, b"contract", &z));
+        assert!(!disavow(&cp, &x, &y, b"contract", &z, 8));
+
+        // a value that is not x's signature on the message
+        let other = ChaumPedersen::generate_random_below(&cp.q);
+        let forged = sign(&cp, &other, b"contract");
+        assert!(!confirm(&cp, &x, &y, b"contract", &forged));
+        assert!(disavow(&cp, &x, &y, b"contract", &forged, 8));
+
+        // -z differs from z by an order-2 factor, invisible when c is even
+        let negated = &cp.p - &z;
+        assert!((0..8).all(|_| !confirm(&cp, &x, &y, b"contract", &negated)));
+    }
+
+    #[test]
+    fn test_signer_cannot_disavow_genuine_signature() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let y = cp.alpha.modpow(&x, &cp.p);
+        let z = sign(&cp, &x, b"contract");
+
+        // the prover refuses outright on its own genuine signature
+        let (_, challenge) = DisavowalVerifier::new(&cp, &y, b"contract", &z);
+        assert!(matches!(
+            DisavowalProver::new(&cp, &x, b"contract", &z, challenge),
+            Err(Error::Verification(_))
+        ));
+
+        // run with any other key, it cannot find e and every round fails
+        let other = ChaumPedersen::generate_random_below(&cp.q);
+        assert!(!disavow(&cp, &other, &y, b"contract", &z, 8));
+        let (verifier, challenge) = DisavowalVerifier::new(&cp, &y, b"contract", &z);
+        match DisavowalProver::new(&cp, &other, b"contract", &z, challenge) {
+            Ok((prover, guess_commitment)) => {
+                let (e, f) = verifier.reveal();
+                let (guess, nonce) = prover.open(&cp, &y, b"contract", &z, e, &f).unwrap();
+                assert!(!verifier.accept(&guess_commitment, guess, &nonce));
+            }
+            Err(error) => assert!(matches!(error, Error::Verification(_))),
+        }
+    }
+
+    #[test]
+    fn test_confirmation_is_not_transferable() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+        let y = cp.alpha.modpow(&x, &cp.p);
+
+        // a real transcript checks out for a third party...
+        let z = sign(&cp, &x, b"contract");
+        let (verifier, challenge_commitment) = ConfirmationVerifier::new(&cp);
+        let (prover, (r1, r2)) = ConfirmationProver::new(&cp, b"contract", challenge_commitment);
+        let (c, nonce) = verifier.open();
+        let s = prover.respond(&cp, &x, &c, &nonce).unwrap();
+        let real = ConfirmationTranscript {
+            challenge_commitment,
+            r1,
+            r2,
+            c,
+            nonce,
+            s,
+        };
+        assert!(transcript_is_consistent(&cp, &y, b"contract", &z, &real));
+
+        // ...but so does one the verifier made up for a signature that is false
+        let other = ChaumPedersen::generate_random_below(&cp.q);
+        let forged = sign(&cp, &other, b"contract");
+        let fake = simulate_confirmation(&cp, &y, b"contract", &forged);
+        assert!(transcript_is_consistent(
+            &cp,
+            &y,
+            b"contract",
+            &forged,
+            &fake
+        ));
+    }
+
+    #[test]
+    fn test_prover_rejects_changed_challenge() {
+        let cp = constants();
+        let x = ChaumPedersen::generate_random_below(&cp.q);
+
+        let (verifier, challenge_commitment) = ConfirmationVerifier::new(&cp);
+        let (prover, _) = ConfirmationProver::new(&cp, b"contract", challenge_commitment);
+        let (c, nonce) = verifier.open();
+
+        let adaptive = (c + 1u32) % &cp.q;
+        assert!(matches!(
+            prover.respond(&cp, &x, &adaptive, &nonce),
+            Err(Error::Verification(_))
+        ));
+    }
+}
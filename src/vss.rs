@@ -0,0 +1,219 @@
+//! Feldman and Pedersen verifiable secret sharing over the Chaum-Pedersen group.
+//!
+//! The dealer shares s with a random polynomial f of degree t-1 and publishes
+//! commitments to its coefficients, so every custodian can check its share
+//! f(i) before accepting it. Feldman commits with C_j = alpha^a_j (s itself is
+//! only computationally hidden); Pedersen adds a second polynomial g and
+//! commits with C_j = alpha^a_j * h^b_j, which hides s perfectly whatever h
+//! is. The commitments only bind the dealer to one polynomial while
+//! log_alpha(h) is unknown to it, so h is hashed into the group rather than
+//! taken to be beta, whose log the group definition fixes publicly.
+
+use crate::poly::{check_threshold, interpolate, Polynomial};
+use crate::{ChaumPedersen, Result};
+use num_bigint::BigUint;
+
+/// Share f(i) of custodian i
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub id: u32,
+    pub value: BigUint,
+}
+
+/// Share f(i) together with the blinding value g(i)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PedersenShare {
+    pub share: Share,
+    pub blinding: BigUint,
+}
+
+/// h for Pedersen commitments, an element nobody knows log_alpha of
+pub fn pedersen_generator(cp: &ChaumPedersen) -> BigUint {
+    cp.hash_to_group(&[b"vss-pedersen-h"])
+}
+
+/// prod_j C_j^(i^j) mod p, which equals the commitment to f(i)
+pub fn evaluate_commitments(cp: &ChaumPedersen, commitments: &[BigUint], id: u32) -> BigUint {
+    let id = BigUint::from(id);
+    let mut power = BigUint::from(1u32);
+    let mut result = BigUint::from(1u32);
+
+    for commitment in commitments {
+        result = (result * commitment.modpow(&power, &cp.p)) % &cp.p;
+        power = (power * &id) % &cp.q;
+    }
+
+    result
+}
+
+/// C_j = alpha^a_j for each coefficient a_j of f, plus shares f(1)..f(n);
+/// fails unless 1 <= threshold <= n
+pub fn feldman_deal(
+    cp: &ChaumPedersen,
+    secret: &BigUint,
+    threshold: usize,
+    n: u32,
+) -> Result<(Vec<BigUint>, Vec<Share>)> {
+    check_threshold(threshold, n)?;
+
+    let f = Polynomial::random(secret, threshold - 1, &cp.q);
+
+    let commitments = f
+        .coefficients
+        .iter()
+        .map(|a| cp.alpha.modpow(a, &cp.p))
+        .collect();
+
+    let shares = (1..=n)
+        .map(|id| Share {
+            id,
+            value: f.evaluate(&BigUint::from(id)),
+        })
+        .collect();
+
+    Ok((commitments, shares))
+}
+
+/// alpha^f(i) == prod_j C_j^(i^j)
+pub fn feldman_verify(cp: &ChaumPedersen, commitments: &[BigUint], share: &Share) -> bool {
+    cp.alpha.modpow(&share.value, &cp.p) == evaluate_commitments(cp, commitments, share.id)
+}
+
+/// C_j = alpha^a_j * h^b_j for coefficients a_j of f and b_j of g; fails
+/// unless 1 <= threshold <= n
+pub fn pedersen_deal(
+    cp: &ChaumPedersen,
+    secret: &BigUint,
+    threshold: usize,
+    n: u32,
+) -> Result<(Vec<BigUint>, Vec<PedersenShare>)> {
+    check_threshold(threshold, n)?;
+
+    let f = Polynomial::random(secret, threshold - 1, &cp.q);
+    let g = Polynomial::random(
+        &ChaumPedersen::generate_random_below(&cp.q),
+        threshold - 1,
+        &cp.q,
+    );
+
+    let h = pedersen_generator(cp);
+    let commitments = f
+        .coefficients
+        .iter()
+        .zip(g.coefficients.iter())
+        .map(|(a, b)| (cp.alpha.modpow(a, &cp.p) * h.modpow(b, &cp.p)) % &cp.p)
+        .collect();
+
+    let shares = (1..=n)
+        .map(|id| {
+            let z = BigUint::from(id);
+            PedersenShare {
+                share: Share {
+                    id,
+                    value: f.evaluate(&z),
+                },
+                blinding: g.evaluate(&z),
+            }
+        })
+        .collect();
+
+    Ok((commitments, shares))
+}
+
+/// alpha^f(i) * h^g(i) == prod_j C_j^(i^j)
+pub fn pedersen_verify(cp: &ChaumPedersen, commitments: &[BigUint], share: &PedersenShare) -> bool {
+    let h = pedersen_generator(cp);
+    let lhs =
+        (cp.alpha.modpow(&share.share.value, &cp.p) * h.modpow(&share.blinding, &cp.p)) % &cp.p;
+
+    lhs == evaluate_commitments(cp, commitments, share.share.id)
+}
+
+/// s = f(0) by Lagrange interpolation mod q over at least t distinct shares;
+/// fails on a repeated id
+pub fn reconstruct(cp: &ChaumPedersen, shares: &[Share]) -> Result<BigUint> {
+    let points: Vec<(u32, BigUint)> = shares
+        .iter()
+        .map(|share| (share.id, share.value.clone()))
+        .collect();
+
+    interpolate(&points, &cp.q)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn constants() -> ChaumPedersen {
+        let (alpha, beta, p, q) = ChaumPedersen::get_constants();
+        ChaumPedersen { alpha, beta, p, q }
+    }
+
+    #[test]
+    fn test_feldman_share_verify_and_reconstruct() {
+        let cp = constants();
+        let secret = ChaumPedersen::generate_random_below(&cp.q);
+
+        let (commitments, shares) = feldman_deal(&cp, &secret, 3, 5).unwrap();
+        assert_eq!(commitments[0], cp.alpha.modpow(&secret, &cp.p));
+
+        for share in &shares {
+            assert!(feldman_verify(&cp, &commitments, share));
+        }
+
+        let mut tampered = shares[1].clone();
+        tampered.value = (tampered.value + 1u32) % &cp.q;
+        assert!(!feldman_verify(&cp, &commitments, &tampered));
+
+        assert_eq!(reconstruct(&cp, &shares[..3]), Ok(secret.clone()));
+        assert_eq!(reconstruct(&cp, &shares[2..]), Ok(secret.clone()));
+        assert_ne!(reconstruct(&cp, &shares[..2]), Ok(secret));
+
+        // a repeated share would zero a Lagrange denominator
+        let repeated = [shares[0].clone(), shares[1].clone(), shares[1].clone()];
+        assert!(matches!(
+            reconstruct(&cp, &repeated),
+            Err(crate::Error::InvalidParameters(_))
+        ));
+    }
+
+    #[test]
+    fn test_pedersen_share_verify_and_reconstruct() {
+        let cp = constants();
+        let secret = ChaumPedersen::generate_random_below(&cp.q);
+
+        let (commitments, shares) = pedersen_deal(&cp, &secret, 2, 4).unwrap();
+
+        let h = pedersen_generator(&cp);
+        assert!(cp.is_element(&h));
+        assert!(h != cp.alpha && h != cp.beta);
+
+        for share in &shares {
+            assert!(pedersen_verify(&cp, &commitments, share));
+        }
+
+        let mut tampered = shares[3].clone();
+        tampered.blinding = (tampered.blinding + 1u32) % &cp.q;
+        assert!(!pedersen_verify(&cp, &commitments, &tampered));
+
+        let plain: Vec<Share> = shares.iter().map(|s| s.share.clone()).collect();
+        assert_eq!(
+            reconstruct(&cp, &[plain[3].clone(), plain[0].clone()]),
+            Ok(secret)
+        );
+    }
+
+    #[test]
+    fn test_bad_threshold_is_rejected() {
+        let cp = constants();
+        let secret = BigUint::from(7u32);
+        assert!(matches!(
+            feldman_deal(&cp, &secret, 0, 3),
+            Err(crate::Error::InvalidParameters(_))
+        ));
+        assert!(matches!(
+            pedersen_deal(&cp, &secret, 4, 3),
+            Err(crate::Error::InvalidParameters(_))
+        ));
+    }
+}
@@ -0,0 +1,365 @@
+//! `wasm-bindgen` bindings for a browser-side prover, enabled by the `wasm`
+//! feature and packaged by the `nmc-solution-wasm` crate in `wasm/`
+//! (`wasm-pack build wasm`).
+//!
+//! The password never leaves the browser: x is derived from it locally with
+//! the user's `Kdf` and only y1, y2, r1, r2 and s are sent. Every byte array
+//! is the proto's fixed-length big-endian encoding for the prover's `Group`,
+//! so a gRPC-web client talks to `nmc-server` with the same messages
+//! `nmc-client` sends:
+//!
+//! ```js
+//! const { groups } = await client.getParameters({});
+//! const group = groups.map((offered) => Group.fromId(offered.groupId))
+//!   .filter((known) => known).pop();
+//! const kdf = Kdf.generate();
+//! const pair = new Prover(password, kdf, group).register();
+//! await client.register({ user, y1: pair.y1, y2: pair.y2, groupId: group.id,
+//!   kdf: { algorithm: kdf.algorithm, salt: kdf.salt, iterations: kdf.iterations } });
+//!
+//! const { groupId, kdf: recorded } = await client.getUserParameters({ user });
+//! const prover = new Prover(password, Kdf.fromParameters(
+//!   recorded.algorithm, recorded.salt, recorded.iterations), Group.fromId(groupId));
+//! const commitment = prover.commit();
+//! const { authId, c } = await client.createAuthenticationChallenge(
+//!   { user, r1: commitment.r1, r2: commitment.r2, groupId });
+//! await client.verifyAuthentication({ authId, s: prover.answer(c), groupId });
+//! ```
+
+use crate::{groups::GroupId, kdf, ChaumPedersen};
+use alloc::{string::String, vec::Vec};
+use num_bigint::BigUint;
+use rand::rngs::OsRng;
+use wasm_bindgen::prelude::*;
+
+/// Two group elements, e.g. (y1, y2) or (r1, r2)
+#[wasm_bindgen]
+pub struct Pair {
+    first: Vec<u8>,
+    second: Vec<u8>,
+}
+
+impl Pair {
+    fn new(cp: &ChaumPedersen, (first, second): (BigUint, BigUint)) -> Pair {
+        Pair {
+            first: cp.encode_element(&first),
+            second: cp.encode_element(&second),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Pair {
+    #[wasm_bindgen(getter)]
+    pub fn y1(&self) -> Vec<u8> {
+        self.first.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y2(&self) -> Vec<u8> {
+        self.second.clone()
+    }
+
+    /// Same as y1, named for a commitment
+    #[wasm_bindgen(getter)]
+    pub fn r1(&self) -> Vec<u8> {
+        self.y1()
+    }
+
+    /// Same as y2, named for a commitment
+    #[wasm_bindgen(getter)]
+    pub fn r2(&self) -> Vec<u8> {
+        self.y2()
+    }
+
+    #[wasm_bindgen(getter, js_name = y1Hex)]
+    pub fn y1_hex(&self) -> String {
+        hex::encode(self.y1())
+    }
+
+    #[wasm_bindgen(getter, js_name = y2Hex)]
+    pub fn y2_hex(&self) -> String {
+        hex::encode(self.y2())
+    }
+}
+
+/// The group a user registers in: GetParameters lists those the server
+/// offers and GetUserParameters returns the one it recorded
+#[wasm_bindgen]
+pub struct Group(GroupId);
+
+#[wasm_bindgen]
+impl Group {
+    /// undefined for a `group_id` these bindings do not know
+    #[wasm_bindgen(js_name = fromId)]
+    pub fn from_id(id: u32) -> Option<Group> {
+        GroupId::try_from(id).ok().map(Group)
+    }
+
+    /// The `group_id` to put in every message
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> u32 {
+        self.0.into()
+    }
+}
+
+/// How x is derived from the password: the `KdfParameters` the server
+/// records at registration and returns from GetUserParameters
+#[wasm_bindgen]
+pub struct Kdf(kdf::Kdf);
+
+/// `KdfAlgorithm` values in the proto
+const KDF_RAW: u32 = 0;
+const KDF_PBKDF2_SHA256: u32 = 1;
+
+#[wasm_bindgen]
+impl Kdf {
+    /// PBKDF2 with a salt from the browser's crypto.getRandomValues, for
+    /// a new registration
+    pub fn generate() -> Kdf {
+        Kdf(kdf::Kdf::pbkdf2_sha256_with(&mut OsRng))
+    }
+
+    /// The password's bytes as x, for users registered before PBKDF2
+    pub fn raw() -> Kdf {
+        Kdf(kdf::Kdf::Raw)
+    }
+
+    /// undefined for an unknown algorithm or settings the server would
+    /// refuse
+    #[wasm_bindgen(js_name = fromParameters)]
+    pub fn from_parameters(algorithm: u32, salt: &[u8], iterations: u32) -> Option<Kdf> {
+        let kdf = match algorithm {
+            KDF_RAW => kdf::Kdf::Raw,
+            KDF_PBKDF2_SHA256 => kdf::Kdf::Pbkdf2Sha256 {
+                salt: salt.to_vec(),
+                iterations,
+            },
+            _ => return None,
+        };
+        kdf.validate().ok()?;
+        Some(Kdf(kdf))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn algorithm(&self) -> u32 {
+        match self.0 {
+            kdf::Kdf::Raw => KDF_RAW,
+            kdf::Kdf::Pbkdf2Sha256 { .. } => KDF_PBKDF2_SHA256,
+        }
+    }
+
+    /// Empty for `raw`
+    #[wasm_bindgen(getter)]
+    pub fn salt(&self) -> Vec<u8> {
+        match &self.0 {
+            kdf::Kdf::Raw => Vec::new(),
+            kdf::Kdf::Pbkdf2Sha256 { salt, .. } => salt.clone(),
+        }
+    }
+
+    /// 0 for `raw`
+    #[wasm_bindgen(getter)]
+    pub fn iterations(&self) -> u32 {
+        match self.0 {
+            kdf::Kdf::Raw => 0,
+            kdf::Kdf::Pbkdf2Sha256 { iterations, .. } => iterations,
+        }
+    }
+}
+
+/// x from the password, as `nmc-client` derives it with the same `kdf`
+#[wasm_bindgen(js_name = deriveSecret)]
+pub fn derive_secret(password: &str, kdf: &Kdf) -> Vec<u8> {
+    kdf.0.derive(password.as_bytes()).to_bytes_be()
+}
+
+/// (alpha^exp mod p, beta^exp mod p)
+#[wasm_bindgen(js_name = computePair)]
+pub fn compute_pair(exp: &[u8], group: &Group) -> Pair {
+    let cp = group.0.parameters();
+    let pair = cp.compute_pair(&BigUint::from_bytes_be(exp));
+    Pair::new(&cp, pair)
+}
+
+/// s = k - c * x mod q
+#[wasm_bindgen]
+pub fn solve(k: &[u8], c: &[u8], x: &[u8], group: &Group) -> Vec<u8> {
+    let cp = group.0.parameters();
+    let s = cp.solve(
+        &BigUint::from_bytes_be(k),
+        &BigUint::from_bytes_be(c),
+        &BigUint::from_bytes_be(x),
+    );
+    cp.encode_scalar(&s)
+}
+
+/// A random k below q from the browser's crypto.getRandomValues
+#[wasm_bindgen(js_name = randomNonce)]
+pub fn random_nonce(group: &Group) -> Vec<u8> {
+    let cp = group.0.parameters();
+    cp.encode_scalar(&ChaumPedersen::generate_random_below_with(
+        &mut OsRng, &cp.q,
+    ))
+}
+
+#[wasm_bindgen(js_name = toHex)]
+pub fn to_hex(bytes: &[u8]) -> String {
+    hex::encode(bytes)
+}
+
+/// undefined when the string is not hex
+#[wasm_bindgen(js_name = fromHex)]
+pub fn from_hex(value: &str) -> Option<Vec<u8>> {
+    hex::decode(value).ok()
+}
+
+/// Holds x and the pending nonce k for one login
+#[wasm_bindgen]
+pub struct Prover {
+    cp: ChaumPedersen,
+    x: BigUint,
+    k: Option<BigUint>,
+}
+
+#[wasm_bindgen]
+impl Prover {
+    #[wasm_bindgen(constructor)]
+    pub fn new(password: &str, kdf: &Kdf, group: &Group) -> Prover {
+        Prover {
+            cp: group.0.parameters(),
+            x: kdf.0.derive(password.as_bytes()),
+            k: None,
+        }
+    }
+
+    /// (y1, y2) for RegisterRequest
+    pub fn register(&self) -> Pair {
+        Pair::new(&self.cp, self.cp.compute_pair(&self.x))
+    }
+
+    /// (r1, r2) for AuthenticationChallengeRequest, drawing a fresh k
+    pub fn commit(&mut self) -> Pair {
+        let k = ChaumPedersen::generate_random_below_with(&mut OsRng, &self.cp.q);
+        let pair = Pair::new(&self.cp, self.cp.compute_pair(&k));
+        self.k = Some(k);
+        pair
+    }
+
+    /// s for AuthenticationAnswerRequest, undefined without a pending commit
+    pub fn answer(&mut self, c: &[u8]) -> Option<Vec<u8>> {
+        let k = self.k.take()?;
+        let s = self.cp.solve(&k, &BigUint::from_bytes_be(c), &self.x);
+        Some(self.cp.encode_scalar(&s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    /// `Kdf::MIN_ITERATIONS` keeps the tests quick
+    fn test_kdf() -> Kdf {
+        Kdf::from_parameters(
+            KDF_PBKDF2_SHA256,
+            &[7; kdf::Kdf::SALT_LEN],
+            kdf::Kdf::MIN_ITERATIONS,
+        )
+        .unwrap()
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_login_flow() {
+        // a group other than the first, as recorded for a newer user
+        let group = Group::from_id(2).unwrap();
+        let cp = GroupId::Ffdhe2048.parameters();
+        let mut prover = Prover::new("password", &test_kdf(), &group);
+
+        let registered = prover.register();
+        let commitment = prover.commit();
+        let c = random_nonce(&group);
+        let s = prover.answer(&c).unwrap();
+
+        // everything decodes as the server decodes it
+        let element = |bytes: Vec<u8>| cp.decode_element(&bytes).unwrap();
+        assert!(cp.verify(
+            &element(registered.y1()),
+            &element(registered.y2()),
+            &element(commitment.r1()),
+            &element(commitment.r2()),
+            &cp.decode_scalar(&c).unwrap(),
+            &cp.decode_scalar(&s).unwrap(),
+        ));
+        assert_eq!(group.id(), 2);
+
+        // each commit answers one challenge
+        assert!(prover.answer(&c).is_none());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_free_functions_match_prover() {
+        let group = Group::from_id(1).unwrap();
+        let x = derive_secret("password", &test_kdf());
+        let registered = Prover::new("password", &test_kdf(), &group).register();
+        let pair = compute_pair(&x, &group);
+        assert_eq!(pair.y1(), registered.y1());
+        assert_eq!(pair.y2_hex(), to_hex(&registered.y2()));
+
+        let k = random_nonce(&group);
+        let c = random_nonce(&group);
+        let cp = GroupId::Rfc5114.parameters();
+        let (r1, r2) = cp.compute_pair(&BigUint::from_bytes_be(&k));
+        let s = BigUint::from_bytes_be(&solve(&k, &c, &x, &group));
+        assert!(cp.verify(
+            &BigUint::from_bytes_be(&pair.y1()),
+            &BigUint::from_bytes_be(&pair.y2()),
+            &r1,
+            &r2,
+            &BigUint::from_bytes_be(&c),
+            &s,
+        ));
+
+        assert_eq!(from_hex(&to_hex(&x)), Some(x));
+        assert_eq!(from_hex("not hex"), None);
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_group_ids() {
+        for id in GroupId::ALL {
+            assert_eq!(Group::from_id(id.into()).unwrap().id(), u32::from(id));
+        }
+        assert!(Group::from_id(0).is_none());
+        assert!(Group::from_id(5).is_none());
+
+        // the same password gives different keys in different groups
+        let registered = |id| Prover::new("password", &Kdf::raw(), &Group::from_id(id).unwrap());
+        assert_ne!(registered(1).register().y1(), registered(2).register().y1());
+    }
+
+    #[wasm_bindgen_test(unsupported = test)]
+    fn test_kdf_parameters() {
+        let kdf = test_kdf();
+        assert_eq!(kdf.algorithm(), KDF_PBKDF2_SHA256);
+        let copy = Kdf::from_parameters(kdf.algorithm(), &kdf.salt(), kdf.iterations()).unwrap();
+        assert_eq!(
+            derive_secret("password", &copy),
+            derive_secret("password", &kdf)
+        );
+        assert_ne!(
+            derive_secret("password", &kdf),
+            derive_secret("password", &Kdf::raw())
+        );
+
+        let raw = Kdf::raw();
+        assert!(Kdf::from_parameters(raw.algorithm(), &raw.salt(), raw.iterations()).is_some());
+        assert_eq!(derive_secret("password", &raw), b"password".to_vec());
+
+        assert_eq!(Kdf::generate().algorithm(), KDF_PBKDF2_SHA256);
+        assert_ne!(Kdf::generate().salt(), Kdf::generate().salt());
+
+        // what the server would refuse at registration
+        assert!(Kdf::from_parameters(2, &[], 0).is_none());
+        assert!(Kdf::from_parameters(KDF_PBKDF2_SHA256, &kdf.salt(), 1).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+/*
+ * Register, prove and verify through the C ABI, as a C++ verifier embedding
+ * the library would. Build and run with `make -C tests/c`.
+ */
+
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "nmc_solution.h"
+
+#define MAX_LEN 512
+
+typedef struct {
+    uint8_t bytes[MAX_LEN];
+    size_t len;
+} buffer;
+
+static buffer empty(void) {
+    buffer b;
+    b.len = MAX_LEN;
+    return b;
+}
+
+static void test_interactive(const NmcParameters *params) {
+    const uint8_t *x = (const uint8_t *)"password";
+    size_t x_len = strlen("password");
+
+    /* register: y1 = alpha^x, y2 = beta^x */
+    buffer y1 = empty(), y2 = empty();
+    assert(nmc_compute_pair(params, x, x_len, y1.bytes, &y1.len, y2.bytes, &y2.len) ==
+           NMC_STATUS_OK);
+
+    /* commit: r1 = alpha^k, r2 = beta^k */
+    buffer k = empty(), r1 = empty(), r2 = empty();
+    assert(nmc_random_scalar(params, k.bytes, &k.len) == NMC_STATUS_OK);
+    assert(nmc_compute_pair(params, k.bytes, k.len, r1.bytes, &r1.len, r2.bytes, &r2.len) ==
+           NMC_STATUS_OK);
+
+    /* challenge and answer: s = k - c * x mod q */
+    buffer c = empty(), s = empty();
+    assert(nmc_random_scalar(params, c.bytes, &c.len) == NMC_STATUS_OK);
+    assert(nmc_solve(params, k.bytes, k.len, c.bytes, c.len, x, x_len, s.bytes, &s.len) ==
+           NMC_STATUS_OK);
+
+    assert(nmc_verify(params, y1.bytes, y1.len, y2.bytes, y2.len, r1.bytes, r1.len, r2.bytes,
+                      r2.len, c.bytes, c.len, s.bytes, s.len) == NMC_STATUS_OK);
+
+    /* an answer for another challenge is rejected */
+    c.bytes[c.len - 1] ^= 1;
+    assert(nmc_verify(params, y1.bytes, y1.len, y2.bytes, y2.len, r1.bytes, r1.len, r2.bytes,
+                      r2.len, c.bytes, c.len, s.bytes, s.len) == NMC_STATUS_INVALID);
+
+    printf("interactive login: ok\n");
+}
+
+static void test_non_interactive(const NmcParameters *params) {
+    const uint8_t *x = (const uint8_t *)"password";
+    size_t x_len = strlen("password");
+
+    buffer y1 = empty(), y2 = empty();
+    assert(nmc_compute_pair(params, x, x_len, y1.bytes, &y1.len, y2.bytes, &y2.len) ==
+           NMC_STATUS_OK);
+
+    NmcProof *proof = NULL;
+    assert(nmc_prove(params, x, x_len, &proof) == NMC_STATUS_OK);
+
+    /* the proof survives a round trip through its bytes */
+    buffer r1 = empty(), r2 = empty(), s = empty();
+    assert(nmc_proof_parts(proof, r1.bytes, &r1.len, r2.bytes, &r2.len, s.bytes, &s.len) ==
+           NMC_STATUS_OK);
+    NmcProof *received = NULL;
+    assert(nmc_proof_new(r1.bytes, r1.len, r2.bytes, r2.len, s.bytes, s.len, &received) ==
+           NMC_STATUS_OK);
+
+    assert(nmc_verify_proof(params, y1.bytes, y1.len, y2.bytes, y2.len, received) ==
+           NMC_STATUS_OK);
+    assert(nmc_verify_proof(params, y2.bytes, y2.len, y1.bytes, y1.len, received) ==
+           NMC_STATUS_INVALID);
+
+    nmc_proof_free(received);
+    nmc_proof_free(proof);
+    printf("non-interactive proof: ok\n");
+}
+
+static void test_errors(const NmcParameters *params) {
+    NmcParameters *unknown = NULL;
+    assert(nmc_parameters_standard(512, &unknown) == NMC_STATUS_INVALID_PARAMETERS);
+    assert(unknown == NULL);
+
+    uint8_t small[4];
+    size_t small_len = sizeof(small);
+    assert(nmc_random_scalar(params, small, &small_len) == NMC_STATUS_BUFFER_TOO_SMALL);
+    assert(nmc_random_scalar(NULL, small, &small_len) == NMC_STATUS_NULL_POINTER);
+
+    printf("error codes: ok\n");
+}
+
+int main(void) {
+    NmcParameters *params = NULL;
+    assert(nmc_parameters_standard(1024, &params) == NMC_STATUS_OK);
+    assert(nmc_element_len(params) <= MAX_LEN);
+
+    test_interactive(params);
+    test_non_interactive(params);
+    test_errors(params);
+
+    nmc_parameters_free(params);
+    return 0;
+}
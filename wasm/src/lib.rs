@@ -0,0 +1,4 @@
+//! The browser-side prover of `nmc_solution::wasm`, packaged for
+//! `wasm-pack build`.
+
+pub use nmc_solution::wasm::*;